@@ -6,9 +6,40 @@ script.on_name(function()
 end)
 script.display_name_only()
 
-script.on_activate(function(game, _buffer)
+local function count_easy_cells(game)
+	local small_count = 0
+	for i, v in pairs(game:cells()) do
+		if v == 0 and #(game:unoccupied_cells_at(i - 1)) == 1 then
+			small_count = small_count + 1
+		end
+	end
+	return small_count
+end
+
+local function find_board()
+	commands.run_async("BoardGen", function(game, status, buffer)
+		if status ~= "Ok" then
+			Active = false
+			error("Board generation failed: " .. buffer)
+			return
+		end
+
+		local small_count = count_easy_cells(game)
+		if small_count < 7 then
+			find_board()
+		else
+			Active = false
+			commands.run('Eval "Found with ' .. small_count .. ' free"')
+			events.wait_ms(800, function(_game)
+				commands.run('Eval ""')
+			end)
+		end
+	end)
+end
+
+script.on_activate(function(_game, _buffer)
 	Active = true
-	game:enter_buffer_command("BoardGen")
+	find_board()
 end)
 
 script.on_update(function(_game)
@@ -18,25 +49,3 @@ script.on_update(function(_game)
 		return "", "StatusBarItemOkay"
 	end
 end)
-
-events.on_board_gen(function(game)
-	if not Active then
-		return
-	end
-
-	local small_count = 0
-	for i, v in pairs(game:cells()) do
-		if v == 0 and #(game:unoccupied_cells_at(i - 1)) == 1 then
-			small_count = small_count + 1
-		end
-	end
-	if small_count < 7 then
-		game:enter_buffer_command("BoardGen")
-	else
-		Active = false
-		game:enter_buffer_command('Eval "Found with ' .. small_count .. ' free"')
-		events.wait_ms(800, function(game)
-			game:enter_buffer_command('Eval ""')
-		end)
-	end
-end)
@@ -0,0 +1,17 @@
+-- Generates a new board by relabelling the digits of a known board, use with `BoardGen gen=relabelled`
+local BOARD = "530070000600195000098000060800060003400803001700020006060000280000419005000080079"
+
+script.display_none()
+
+generators.register("relabelled", function(seed)
+	local digits = { 1, 2, 3, 4, 5, 6, 7, 8, 9 }
+	for i = #digits, 2, -1 do
+		seed = (seed * 16807 + 1) % 2147483647
+		local j = seed % i + 1
+		digits[i], digits[j] = digits[j], digits[i]
+	end
+
+	return (BOARD:gsub("[1-9]", function(digit)
+		return tostring(digits[tonumber(digit)])
+	end))
+end)
@@ -1,5 +1,5 @@
-StartMS = __systime_ms__()
-EndMS = __systime_ms__()
+StartMS = __gametime_ms__()
+EndMS = __gametime_ms__()
 Solved = false
 
 script.display_status_only()
@@ -8,18 +8,33 @@ script.on_name(function()
 end)
 
 script.on_update(function(game)
+	local best = storage.get("best_ms")
+	local best_text = ""
+	if best then
+		best_text = " (best " .. best .. "ms)"
+	end
+
 	if game:is_solved() then
 		if not Solved then
-			EndMS = __systime_ms__()
+			EndMS = __gametime_ms__()
 			Solved = true
+			if not best or EndMS - StartMS < best then
+				storage.set("best_ms", EndMS - StartMS)
+			end
 		end
-		return ((EndMS - StartMS) .. "ms"), "StatusBarItemOkay"
+		return ((EndMS - StartMS) .. "ms" .. best_text), "StatusBarItemOkay"
 	else
-		return ((__systime_ms__() - StartMS) .. "ms"), "StatusBarItemInProgress"
+		return ((__gametime_ms__() - StartMS) .. "ms" .. best_text), "StatusBarItemInProgress"
 	end
 end)
 
 events.on_board_gen(function(_)
-	StartMS = __systime_ms__()
+	StartMS = __gametime_ms__()
 	Solved = false
 end)
+
+events.on_reload(function(_, previous)
+	StartMS = previous.StartMS or StartMS
+	EndMS = previous.EndMS or EndMS
+	Solved = previous.Solved or Solved
+end)
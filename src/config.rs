@@ -1,4 +1,7 @@
-use std::{io::Write, path::PathBuf};
+use std::{
+    io::Write,
+    path::{Component, PathBuf},
+};
 
 use directories::ProjectDirs;
 use tracing::{debug, info, span, trace, Level};
@@ -20,6 +23,15 @@ pub fn get_file_path(name: &str) -> PathBuf {
         .expect("unable to find root config directory")
 }
 
+/// The path of `name` in the config directory, or `None` if it's absolute or contains `..`, so
+/// that sandboxed scripts can't reach files outside of the config directory
+pub fn get_sandboxed_file_path(name: &str) -> Option<PathBuf> {
+    let path = std::path::Path::new(name);
+    path.components()
+        .all(|x| matches!(x, Component::Normal(_) | Component::CurDir))
+        .then(|| get_file_path(name))
+}
+
 pub fn get_file(name: &str, default: Option<&[u8]>) -> Option<Vec<u8>> {
     let file_path = get_file_path(name);
     info!("Loading file from: {}", file_path.display());
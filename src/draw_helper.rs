@@ -1,4 +1,4 @@
-use std::{cell::Cell, collections::HashMap, rc::Rc, str::FromStr, sync::Mutex};
+use std::{cell::Cell, collections::HashMap, ops::Range, rc::Rc, str::FromStr, sync::Mutex};
 
 use macroquad::{
     color::Color,
@@ -10,10 +10,16 @@ const STATUS_BAR_PERCENTAGE: f32 = 0.03;
 const NORMAL_LINE_PERCENTAGE: f32 = 0.001;
 const BOX_LINE_PERCENTAGE: f32 = 0.004;
 
-pub fn get_status_bar_height() -> f32 {
+/// The height of a single row of the status bar
+pub fn get_status_bar_row_height() -> f32 {
     screen_height() * STATUS_BAR_PERCENTAGE
 }
 
+/// The height of the status bar, which grows by a row at a time when its items overflow
+pub fn get_status_bar_height(drawing: &DrawingSettings) -> f32 {
+    get_status_bar_row_height() * drawing.status_bar_layout().rows.max(1) as f32
+}
+
 pub fn get_normal_line_width() -> f32 {
     (screen_height() * NORMAL_LINE_PERCENTAGE).max(2.0) as u32 as f32
 }
@@ -30,6 +36,139 @@ pub struct DrawingSettings {
     padding_start: Cell<f32>,
     padding_speed: Cell<f32>,
     font_size: Cell<f32>,
+    layout: Rc<Cell<BoardLayout>>,
+    status_bar_layout: Rc<Cell<StatusBarLayout>>,
+}
+
+/// Where the status bar is placed, how it handles items which don't fit on a single row, and how
+/// many rows it took up on the last frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatusBarLayout {
+    pub top: bool,
+    pub align_right: bool,
+    /// The most rows the status bar grows to, before it has to be scrolled
+    pub max_rows: usize,
+    /// Whether items are collapsed to their names when they don't fit within `max_rows`
+    pub collapse: bool,
+    pub rows: usize,
+}
+
+impl Default for StatusBarLayout {
+    fn default() -> Self {
+        Self {
+            top: false,
+            align_right: false,
+            max_rows: 3,
+            collapse: true,
+            rows: 1,
+        }
+    }
+}
+
+impl StatusBarLayout {
+    /// The top left corner of the status bar
+    pub fn origin(&self) -> (f32, f32) {
+        if self.top {
+            (0.0, 0.0)
+        } else {
+            (
+                0.0,
+                screen_height() - get_status_bar_row_height() * self.rows as f32,
+            )
+        }
+    }
+}
+
+/// Splits entries of `widths` into rows no wider than `available`, returning the range of entries
+/// in each row. An entry wider than `available` gets a row to itself
+pub fn pack_rows(widths: &[f32], available: f32) -> Vec<Range<usize>> {
+    let mut rows = vec![];
+    let mut start = 0;
+    let mut row_width = 0.0;
+    for (i, width) in widths.iter().enumerate() {
+        if i != start && row_width + width > available {
+            rows.push(start..i);
+            start = i;
+            row_width = 0.0;
+        }
+        row_width += width;
+    }
+    rows.push(start..widths.len());
+    rows
+}
+
+/// Where the board was drawn on the last frame, so that items and scripts can line drawings up
+/// with it without re-deriving the grid
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BoardLayout {
+    /// The area the board is drawn within, being the screen minus the status bar
+    pub game_origin: (f32, f32),
+    pub game_size: (f32, f32),
+    /// The top left corner of the board, after the animated padding is applied
+    pub board_origin: (f32, f32),
+    pub cell_size: f32,
+    /// The index of the cell under the mouse
+    pub hovered_cell: Option<u32>,
+}
+
+impl BoardLayout {
+    pub fn new(game_origin: (f32, f32), game_size: (f32, f32), padding: f32) -> Self {
+        let (width, height) = (game_size.0 - padding, game_size.1 - padding);
+        let cell_size = f32::min(width / 9.0, height / 9.0);
+
+        let x_pad = (width - (9.0 * cell_size)) / 2.0;
+        let y_pad = (height - (9.0 * cell_size)) / 2.0;
+
+        Self {
+            game_origin,
+            game_size,
+            board_origin: (
+                game_origin.0 + x_pad + padding / 2.0,
+                game_origin.1 + y_pad + padding / 2.0,
+            ),
+            cell_size,
+            hovered_cell: None,
+        }
+    }
+
+    /// The bounds of the whole board, as x, y, width and height
+    pub fn board_rect(&self) -> (f32, f32, f32, f32) {
+        let size = self.cell_size * 9.0;
+        (self.board_origin.0, self.board_origin.1, size, size)
+    }
+
+    /// The bounds of the cell at `idx`, as x, y, width and height
+    pub fn cell_rect(&self, idx: u32) -> (f32, f32, f32, f32) {
+        (
+            self.board_origin.0 + (idx % 9) as f32 * self.cell_size,
+            self.board_origin.1 + (idx / 9) as f32 * self.cell_size,
+            self.cell_size,
+            self.cell_size,
+        )
+    }
+
+    /// The bounds of box `idx`, counting left to right then top to bottom
+    pub fn box_rect(&self, idx: u32) -> (f32, f32, f32, f32) {
+        let size = self.cell_size * 3.0;
+        (
+            self.board_origin.0 + (idx % 3) as f32 * size,
+            self.board_origin.1 + (idx / 3) as f32 * size,
+            size,
+            size,
+        )
+    }
+
+    /// The index of the cell containing the point `x, y`
+    pub fn cell_at(&self, x: f32, y: f32) -> Option<u32> {
+        let (start_x, start_y, size, _) = self.board_rect();
+        if x > start_x && y > start_y && x < start_x + size && y < start_y + size {
+            let cell_x = ((x - start_x) / self.cell_size) as u32;
+            let cell_y = ((y - start_y) / self.cell_size) as u32;
+            Some(cell_y * 9 + cell_x)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Eq, Hash, PartialEq, Clone, Copy)]
@@ -53,6 +192,63 @@ pub enum AppColour {
     BoardCorrectCell,
     BoardIncorrectCell,
     BoardUnknownCell,
+    BoardCellMark1,
+    BoardCellMark2,
+    BoardCellMark3,
+    BoardCellMark4,
+    BoardCellMark5,
+    BoardCellMark6,
+    BoardCellMark7,
+    BoardCellMark8,
+    BoardCellMark9,
+}
+
+impl AppColour {
+    pub const ALL: [AppColour; 28] = [
+        AppColour::Background,
+        AppColour::StatusBar,
+        AppColour::StatusBarSeparator,
+        AppColour::StatusBarItemSelected,
+        AppColour::StatusBarItemOkay,
+        AppColour::StatusBarItemInProgress,
+        AppColour::StatusBarItemError,
+        AppColour::StatusBarBufferEdit,
+        AppColour::StatusBarItem,
+        AppColour::BoardBox,
+        AppColour::BoardLine,
+        AppColour::BoardCellBackground,
+        AppColour::BoardSelectedCellBackground,
+        AppColour::BoardHighlightedCellBackground,
+        AppColour::BoardMousedCellBackground,
+        AppColour::BoardRadifiedCell,
+        AppColour::BoardCorrectCell,
+        AppColour::BoardIncorrectCell,
+        AppColour::BoardUnknownCell,
+        AppColour::BoardCellMark1,
+        AppColour::BoardCellMark2,
+        AppColour::BoardCellMark3,
+        AppColour::BoardCellMark4,
+        AppColour::BoardCellMark5,
+        AppColour::BoardCellMark6,
+        AppColour::BoardCellMark7,
+        AppColour::BoardCellMark8,
+        AppColour::BoardCellMark9,
+    ];
+
+    pub fn cell_mark(mark: u8) -> Option<Self> {
+        match mark {
+            1 => Some(AppColour::BoardCellMark1),
+            2 => Some(AppColour::BoardCellMark2),
+            3 => Some(AppColour::BoardCellMark3),
+            4 => Some(AppColour::BoardCellMark4),
+            5 => Some(AppColour::BoardCellMark5),
+            6 => Some(AppColour::BoardCellMark6),
+            7 => Some(AppColour::BoardCellMark7),
+            8 => Some(AppColour::BoardCellMark8),
+            9 => Some(AppColour::BoardCellMark9),
+            _ => None,
+        }
+    }
 }
 
 impl FromStr for AppColour {
@@ -78,6 +274,15 @@ impl FromStr for AppColour {
             "BoardCorrectCell" => Ok(AppColour::BoardCorrectCell),
             "BoardIncorrectCell" => Ok(AppColour::BoardIncorrectCell),
             "BoardUnknownCell" => Ok(AppColour::BoardUnknownCell),
+            "BoardCellMark1" => Ok(AppColour::BoardCellMark1),
+            "BoardCellMark2" => Ok(AppColour::BoardCellMark2),
+            "BoardCellMark3" => Ok(AppColour::BoardCellMark3),
+            "BoardCellMark4" => Ok(AppColour::BoardCellMark4),
+            "BoardCellMark5" => Ok(AppColour::BoardCellMark5),
+            "BoardCellMark6" => Ok(AppColour::BoardCellMark6),
+            "BoardCellMark7" => Ok(AppColour::BoardCellMark7),
+            "BoardCellMark8" => Ok(AppColour::BoardCellMark8),
+            "BoardCellMark9" => Ok(AppColour::BoardCellMark9),
             _ => Err(()),
         }
     }
@@ -94,6 +299,8 @@ impl Default for DrawingSettings {
             padding_start: Cell::new(30.0),
             padding_speed: Cell::new(12.0),
             font_size: Cell::new(1.0),
+            layout: Rc::new(Cell::new(BoardLayout::default())),
+            status_bar_layout: Rc::new(Cell::new(StatusBarLayout::default())),
         }
     }
 }
@@ -136,6 +343,15 @@ impl DrawingSettings {
             AppColour::BoardCorrectCell => Color::from_rgba(153, 153, 255, 255),
             AppColour::BoardIncorrectCell => Color::from_rgba(255, 153, 153, 255),
             AppColour::BoardUnknownCell => Color::from_rgba(213, 213, 213, 255),
+            AppColour::BoardCellMark1 => Color::from_rgba(230, 60, 60, 110),
+            AppColour::BoardCellMark2 => Color::from_rgba(240, 150, 40, 110),
+            AppColour::BoardCellMark3 => Color::from_rgba(235, 225, 50, 110),
+            AppColour::BoardCellMark4 => Color::from_rgba(70, 200, 70, 110),
+            AppColour::BoardCellMark5 => Color::from_rgba(60, 210, 210, 110),
+            AppColour::BoardCellMark6 => Color::from_rgba(60, 110, 230, 110),
+            AppColour::BoardCellMark7 => Color::from_rgba(150, 80, 220, 110),
+            AppColour::BoardCellMark8 => Color::from_rgba(230, 100, 190, 110),
+            AppColour::BoardCellMark9 => Color::from_rgba(150, 110, 70, 110),
         }
     }
 
@@ -171,6 +387,34 @@ impl DrawingSettings {
     pub fn set_font_size(&self, size: f32) {
         self.font_size.set(size);
     }
+
+    pub fn layout(&self) -> BoardLayout {
+        self.layout.get()
+    }
+
+    pub fn set_layout(&self, layout: BoardLayout) {
+        self.layout.set(layout);
+    }
+
+    pub fn status_bar_layout(&self) -> StatusBarLayout {
+        self.status_bar_layout.get()
+    }
+
+    pub fn set_status_bar_layout(&self, layout: StatusBarLayout) {
+        self.status_bar_layout.set(layout);
+    }
+
+    /// The width and height of `text` when drawn with the active font at `font_size`
+    pub fn measure_text(&self, text: &str, font_size: f32) -> (f32, f32) {
+        let font = self.font.lock().unwrap();
+        let dim = measure_text(
+            text,
+            Some(&*font),
+            (font_size * self.font_size.get()) as u16,
+            1.0,
+        );
+        (dim.width, dim.height)
+    }
 }
 
 pub fn draw_text_in_bounds(
@@ -216,3 +460,32 @@ pub fn draw_text_in_bounds(
     draw_text_ex(text, x, y + add_height, params);
     (dim.width, dim.height)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn board_layout_geometry() {
+        let layout = BoardLayout::new((0.0, 0.0), (1000.0, 930.0), 30.0);
+        assert_eq!(layout.cell_size, 100.0);
+        assert_eq!(layout.board_rect(), (50.0, 15.0, 900.0, 900.0));
+        assert_eq!(layout.cell_rect(10), (150.0, 115.0, 100.0, 100.0));
+        assert_eq!(layout.box_rect(5), (650.0, 315.0, 300.0, 300.0));
+
+        assert_eq!(layout.cell_at(155.0, 120.0), Some(10));
+        assert_eq!(layout.cell_at(949.0, 914.0), Some(80));
+        assert_eq!(layout.cell_at(40.0, 120.0), None);
+        assert_eq!(layout.cell_at(951.0, 120.0), None);
+    }
+
+    #[test]
+    fn status_bar_rows_pack() {
+        assert_eq!(
+            pack_rows(&[40.0, 50.0, 30.0, 120.0, 10.0], 100.0),
+            vec![0..2, 2..3, 3..4, 4..5]
+        );
+        assert_eq!(pack_rows(&[40.0, 60.0], 100.0), vec![0..2]);
+        assert_eq!(pack_rows(&[], 100.0), vec![0..0]);
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+use tracing::debug;
+
+struct PauseState {
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+    /// Whether the game pauses itself when the window stops being drawn, see `set_auto_pause`
+    auto_pause: bool,
+    /// How long the last frame waited for the window to be ready for the next one
+    frame_wait: Duration,
+}
+
+lazy_static! {
+    static ref PAUSE_STATE: Mutex<PauseState> = Mutex::new(PauseState {
+        paused_at: None,
+        paused_total: Duration::ZERO,
+        auto_pause: true,
+        frame_wait: Duration::ZERO,
+    });
+}
+
+pub fn pause() {
+    pause_since(Instant::now());
+}
+
+/// Pauses the clock, treating time since `since` as already paused
+pub fn pause_since(since: Instant) {
+    let mut state = PAUSE_STATE.lock().unwrap();
+    if state.paused_at.is_none() {
+        debug!("Pausing game clock...");
+        state.paused_at = Some(since);
+    }
+}
+
+pub fn resume() {
+    let mut state = PAUSE_STATE.lock().unwrap();
+    if let Some(paused_at) = state.paused_at.take() {
+        debug!("Resuming game clock...");
+        state.paused_total += Instant::now().duration_since(paused_at);
+    }
+}
+
+pub fn is_paused() -> bool {
+    PAUSE_STATE.lock().unwrap().paused_at.is_some()
+}
+
+/// Sets whether the game pauses itself when the window stops being drawn for a while, such as when
+/// it's minimised or hidden behind other windows
+pub fn set_auto_pause(enabled: bool) {
+    PAUSE_STATE.lock().unwrap().auto_pause = enabled;
+}
+
+pub fn auto_pause() -> bool {
+    PAUSE_STATE.lock().unwrap().auto_pause
+}
+
+/// Records how long the last frame waited for the window, which is only long when the window isn't
+/// being drawn, unlike the frame time which also includes slow work done by the game
+pub fn set_frame_wait(wait: Duration) {
+    PAUSE_STATE.lock().unwrap().frame_wait = wait;
+}
+
+pub fn frame_wait() -> Duration {
+    PAUSE_STATE.lock().unwrap().frame_wait
+}
+
+/// Total time spent paused, including the current pause
+pub fn paused_total() -> Duration {
+    let state = PAUSE_STATE.lock().unwrap();
+    state.paused_total
+        + state
+            .paused_at
+            .map_or(Duration::ZERO, |x| Instant::now().duration_since(x))
+}
+
+/// Milliseconds since the unix epoch, minus all time spent paused
+pub fn game_time_ms() -> u128 {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_millis())
+        .unwrap_or(0);
+    time.saturating_sub(paused_total().as_millis())
+}
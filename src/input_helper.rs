@@ -15,14 +15,16 @@ pub enum InputActionChar {
     Clear,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum InputAction {
     NumberEntered(u8),
+    MarkToggled(u8),
     Function(u8),
     Reset,
     HardReset,
     Clear,
     AutoPlay,
+    Pause,
     MoveUp,
     MoveDown,
     MoveLeft,
@@ -30,20 +32,40 @@ pub enum InputAction {
     ClearBuffer,
     PasteBuffer,
     EnterBuffer,
+    CompleteBuffer,
     UpBuffer,
     DownBuffer,
 }
 
 pub const TYPE_BUFFER_KEY: KeyCode = KeyCode::LeftControl;
+pub const CELL_MARK_KEY: KeyCode = KeyCode::M;
 
 #[derive(Default)]
 pub struct InputState {
     pub enter_buffer: bool,
+    pub drag_cell: Option<(u32, u32)>,
+    /// Set when a status bar item handles the key pressed this frame, hiding it from everything else
+    pub key_consumed: bool,
+    /// Set whilst a status bar item draws over the whole window, so the mouse doesn't select
+    /// cells beneath it
+    pub overlay_open: bool,
 }
 
 impl InputAction {
     fn try_from(value: KeyCode, state: &InputState) -> Result<Self, String> {
         Ok(match value {
+            KeyCode::Key0 | KeyCode::Backspace | KeyCode::Delete if is_key_down(CELL_MARK_KEY) => {
+                InputAction::MarkToggled(0)
+            }
+            KeyCode::Key1 if is_key_down(CELL_MARK_KEY) => InputAction::MarkToggled(1),
+            KeyCode::Key2 if is_key_down(CELL_MARK_KEY) => InputAction::MarkToggled(2),
+            KeyCode::Key3 if is_key_down(CELL_MARK_KEY) => InputAction::MarkToggled(3),
+            KeyCode::Key4 if is_key_down(CELL_MARK_KEY) => InputAction::MarkToggled(4),
+            KeyCode::Key5 if is_key_down(CELL_MARK_KEY) => InputAction::MarkToggled(5),
+            KeyCode::Key6 if is_key_down(CELL_MARK_KEY) => InputAction::MarkToggled(6),
+            KeyCode::Key7 if is_key_down(CELL_MARK_KEY) => InputAction::MarkToggled(7),
+            KeyCode::Key8 if is_key_down(CELL_MARK_KEY) => InputAction::MarkToggled(8),
+            KeyCode::Key9 if is_key_down(CELL_MARK_KEY) => InputAction::MarkToggled(9),
             KeyCode::Key1 => InputAction::NumberEntered(1),
             KeyCode::Key2 => InputAction::NumberEntered(2),
             KeyCode::Key3 => InputAction::NumberEntered(3),
@@ -55,9 +77,12 @@ impl InputAction {
             KeyCode::Key9 => InputAction::NumberEntered(9),
             KeyCode::Backspace | KeyCode::Key0 | KeyCode::Delete => InputAction::Clear,
             KeyCode::Space => InputAction::AutoPlay,
+            KeyCode::P => InputAction::Pause,
             KeyCode::Tab => {
                 if is_key_down(KeyCode::LeftShift) {
                     InputAction::HardReset
+                } else if state.enter_buffer {
+                    InputAction::CompleteBuffer
                 } else {
                     InputAction::Reset
                 }
@@ -109,6 +134,10 @@ impl InputAction {
         let key = get_last_key_pressed();
         let typing_buffer = state.enter_buffer;
 
+        if state.key_consumed {
+            return None;
+        }
+
         match (typing_buffer, ctx) {
             (false, InputActionContext::Generic) | (true, InputActionContext::Buffer) => key,
             _ => None,
@@ -212,6 +241,12 @@ impl InputAction {
         }
     }
 
+    /// The character of the last key pressed, regardless of context or whether it was consumed
+    pub fn get_last_raw_char() -> Option<char> {
+        get_last_key_pressed()
+            .and_then(|x| Self::to_raw_char(x, InputActionContext::Generic, &InputState::default()))
+    }
+
     pub fn get_last_input_char(
         ctx: InputActionContext,
         state: &InputState,
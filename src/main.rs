@@ -2,6 +2,7 @@
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_sign_loss)]
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
 use macroquad::miniquad::window::screen_size;
 use macroquad::prelude::*;
@@ -9,7 +10,7 @@ use tracing::{debug, span, trace, warn, Level};
 
 use draw_helper::{
     draw_text_in_bounds, get_box_line_width, get_normal_line_width, get_status_bar_height,
-    AppColour, DrawingSettings,
+    AppColour, BoardLayout, DrawingSettings,
 };
 use input_helper::{InputAction, InputActionContext};
 use status_bar::{DrawHookData, HookAction, StatusBar};
@@ -17,10 +18,16 @@ use sudoku_game::SudokuGame;
 
 use crate::sudoku_game::ResetSignal;
 
+/// Waiting longer than this for the window to draw the next frame is assumed to be from the window
+/// being minimised or hidden, and pauses the game
+const AUTO_PAUSE_FRAME_WAIT: Duration = Duration::from_secs(1);
+
 mod config;
 mod draw_helper;
+mod game_clock;
 mod input_helper;
 mod status_bar;
+mod storage;
 mod sudoku_game;
 mod task_status;
 
@@ -36,50 +43,66 @@ fn draw_sudoku(game: &mut SudokuGame, drawing: &DrawingSettings, status_bar: &mu
 
     let padding = update_padding(game, drawing);
 
-    let (mut width, mut height) = screen_size();
-
-    run_background_draw_hook(width, height, status_bar);
-
-    height -= get_status_bar_height();
-    let s_padding = padding / 2.0;
-    width -= padding; // padding
-    height -= padding; // padding
-
-    let rect_size = f32::min(width / 9.0, height / 9.0);
+    let (width, height) = screen_size();
 
-    let x_pad = (width - (9.0 * rect_size)) / 2.0;
-    let y_pad = (height - (9.0 * rect_size)) / 2.0;
+    let status_bar_height = get_status_bar_height(drawing);
+    let game_top = if drawing.status_bar_layout().top {
+        status_bar_height
+    } else {
+        0.0
+    };
+    let mut layout = BoardLayout::new(
+        (0.0, game_top),
+        (width, height - status_bar_height),
+        padding,
+    );
+    let (mouse_x, mouse_y) = mouse_position();
+    layout.hovered_cell = layout.cell_at(mouse_x, mouse_y);
+    drawing.set_layout(layout);
 
-    let raw_mouse_pos = mouse_position();
-    let mut mouse_pos: Option<(u32, u32)> = None;
+    run_background_draw_hook(width, height, status_bar);
 
-    if raw_mouse_pos.0 > x_pad + s_padding
-        && raw_mouse_pos.1 > y_pad + s_padding
-        && raw_mouse_pos.0 < x_pad + s_padding + (rect_size * 9.0)
-        && raw_mouse_pos.1 < y_pad + s_padding + (rect_size * 9.0)
-    {
-        mouse_pos = Some((
-            ((raw_mouse_pos.0 - x_pad - s_padding) / (rect_size)) as u32,
-            ((raw_mouse_pos.1 - y_pad - s_padding) / (rect_size)) as u32,
-        ));
-    }
+    let rect_size = layout.cell_size;
+    let mouse_pos = layout
+        .hovered_cell
+        .map(|idx| SudokuGame::idx_pos_to_xy(idx, 9));
 
-    let highlight_cells = match game.selected_cell {
+    let highlight_cells = match game.selected_cell() {
         Some(pos) => game.get_cells_which_see_number_at_pos(pos),
         _ => vec![],
     };
 
-    let mut key = InputAction::get_last_input(InputActionContext::Generic, &game.input);
+    status_bar.run_key_hooks(game);
+    let key = InputAction::get_last_input(InputActionContext::Generic, &game.input);
+
+    if update_pause(game, &key) {
+        let (board_x, board_y, board_size, _) = layout.board_rect();
+        let _ = draw_text_in_bounds(
+            drawing,
+            "Paused",
+            board_x,
+            board_y,
+            rect_size,
+            drawing.colour(AppColour::BoardRadifiedCell),
+            (Some(board_size), Some(board_size)),
+        );
+        return;
+    }
+
+    if !game.input.overlay_open {
+        try_move_selected_from_input(mouse_pos, &key, game);
+    }
+    if let Some(InputAction::MarkToggled(mark)) = key {
+        game.toggle_mark_on_selection(mark);
+    }
 
-    try_move_selected_from_input(mouse_pos, &key, game);
+    let size = game.cells.shape()[1] as u32;
     for (y, row) in game.clone().rows().iter().enumerate() {
         let y = y as f32;
         for (x, _) in row.iter().enumerate() {
             let x = x as f32;
-            let (start_x, start_y) = (
-                x_pad + s_padding + (x * rect_size),
-                y_pad + s_padding + (y * rect_size),
-            );
+            let idx = SudokuGame::xy_pos_to_idx(x as u32, y as u32, size) as usize;
+            let (start_x, start_y, _, _) = layout.cell_rect(idx as u32);
 
             draw_generic_cell_background(
                 (start_x, start_y),
@@ -88,6 +111,7 @@ fn draw_sudoku(game: &mut SudokuGame, drawing: &DrawingSettings, status_bar: &mu
                 (x, y),
                 &highlight_cells,
                 mouse_pos,
+                game.marks[idx],
             );
             draw_cell_text(
                 game,
@@ -95,7 +119,7 @@ fn draw_sudoku(game: &mut SudokuGame, drawing: &DrawingSettings, status_bar: &mu
                 (start_x, start_y),
                 rect_size,
                 drawing,
-                &mut key,
+                &key,
                 status_bar,
             );
             draw_rectangle_lines(
@@ -108,20 +132,13 @@ fn draw_sudoku(game: &mut SudokuGame, drawing: &DrawingSettings, status_bar: &mu
             );
         }
     }
-    let game_copy = game.clone();
-    let boxes = game_copy.boxes();
-    for (index, _) in boxes.iter().enumerate() {
-        let x = (index % boxes.shape()[1]) as f32 * 3.0;
-        let y = (index / boxes.shape()[1]) as f32 * 3.0;
-        let (start_x, start_y) = (
-            x_pad + s_padding + (x * rect_size),
-            y_pad + s_padding + (y * rect_size),
-        );
+    for index in 0..9 {
+        let (start_x, start_y, box_size, _) = layout.box_rect(index);
         draw_rectangle_lines(
             start_x,
             start_y,
-            rect_size * 3.0,
-            rect_size * 3.0,
+            box_size,
+            box_size,
             get_box_line_width(),
             drawing.colour(AppColour::BoardBox),
         );
@@ -156,18 +173,18 @@ fn draw_cell_text(
     start: (f32, f32),
     rect_size: f32,
     drawing: &DrawingSettings,
-    key: &mut Option<InputAction>,
+    key: &Option<InputAction>,
     status_bar: &mut StatusBar<'_>,
 ) {
+    if game.is_selected((pos.0 as u32, pos.1 as u32)) {
+        handle_selected_cell_input(pos, start, rect_size, drawing, key, game);
+    }
+
     let idx = SudokuGame::xy_pos_to_idx(pos.0 as u32, pos.1 as u32, game.cells.shape()[1] as u32)
         as usize;
     let cell = *game.cells.iter().nth(idx).unwrap();
     let unradified = game.unradified.contains(&(idx as u8));
 
-    if let Some((sx, sy)) = game.selected_cell {
-        handle_selected_cell_input(pos, (sx, sy), start, rect_size, drawing, key, game);
-    }
-
     let cancelled = run_cell_text_draw_hook(start, rect_size, status_bar, drawing, game, pos, cell);
 
     if cell != 0 && !cancelled {
@@ -256,42 +273,39 @@ fn run_cell_text_draw_hook(
 
 fn handle_selected_cell_input(
     pos: (f32, f32),
-    size: (u32, u32),
     start: (f32, f32),
     rect_size: f32,
     drawing: &DrawingSettings,
-    key: &mut Option<InputAction>,
+    key: &Option<InputAction>,
     game: &mut SudokuGame,
 ) {
     let idx = SudokuGame::xy_pos_to_idx(pos.0 as u32, pos.1 as u32, game.cells.shape()[1] as u32)
         as usize;
     let cell = game.cells.iter().nth(idx).unwrap();
     let unradified = game.unradified.contains(&(idx as u8));
-    if (pos.0 - size.0 as f32).abs() < f32::EPSILON && (pos.1 - size.1 as f32).abs() < f32::EPSILON
-    {
-        draw_rectangle(
-            start.0,
-            start.1,
-            rect_size,
-            rect_size,
-            drawing.colour(AppColour::BoardSelectedCellBackground),
-        );
-        if unradified {
-            if let Some(ref mut value) = *key {
-                if matches!(value, InputAction::AutoPlay) && *cell == 0 {
-                    // Auto value
-                    do_auto_play(value, pos.0, pos.1, game);
-                }
-                match value {
-                    InputAction::NumberEntered(_) | InputAction::Clear => {
-                        game.cells[(pos.1 as usize, pos.0 as usize)] = match value {
-                            InputAction::NumberEntered(num) => *num,
-                            InputAction::Clear => 0,
-                            _ => panic!("tried to place invalid cell input"),
-                        };
-                    }
-                    _ => {}
+    draw_rectangle(
+        start.0,
+        start.1,
+        rect_size,
+        rect_size,
+        drawing.colour(AppColour::BoardSelectedCellBackground),
+    );
+    if unradified {
+        // Each selected cell works on its own copy, as auto play resolves to a different number per cell
+        if let Some(mut value) = *key {
+            if matches!(value, InputAction::AutoPlay) && *cell == 0 {
+                // Auto value
+                do_auto_play(&mut value, pos.0, pos.1, game);
+            }
+            match value {
+                InputAction::NumberEntered(_) | InputAction::Clear => {
+                    game.cells[(pos.1 as usize, pos.0 as usize)] = match value {
+                        InputAction::NumberEntered(num) => num,
+                        InputAction::Clear => 0,
+                        _ => panic!("tried to place invalid cell input"),
+                    };
                 }
+                _ => {}
             }
         }
     }
@@ -304,6 +318,7 @@ fn draw_generic_cell_background(
     pos: (f32, f32),
     highlight_cells: &[u32],
     mouse_pos: Option<(u32, u32)>,
+    mark: u8,
 ) {
     draw_rectangle(
         start.0,
@@ -313,6 +328,16 @@ fn draw_generic_cell_background(
         drawing.colour(AppColour::BoardCellBackground),
     );
 
+    if let Some(mark_colour) = AppColour::cell_mark(mark) {
+        draw_rectangle(
+            start.0,
+            start.1,
+            rect_size,
+            rect_size,
+            drawing.colour(mark_colour),
+        );
+    }
+
     if highlight_cells.contains(&(pos.1 as u32 * 9 + pos.0 as u32)) {
         draw_rectangle(
             start.0,
@@ -336,6 +361,37 @@ fn draw_generic_cell_background(
     }
 }
 
+fn update_pause(game: &mut SudokuGame, key: &Option<InputAction>) -> bool {
+    // Only the pause key toggles the pause, and it's consumed so that nothing else handles it
+    if let Some(InputAction::Pause) = key {
+        if game_clock::is_paused() {
+            game_clock::resume();
+        } else {
+            game_clock::pause();
+        }
+        game.input.key_consumed = true;
+        return game_clock::is_paused();
+    }
+
+    if game_clock::is_paused() {
+        return true;
+    }
+
+    let frame_wait = game_clock::frame_wait();
+    if game_clock::auto_pause()
+        && frame_wait > AUTO_PAUSE_FRAME_WAIT
+        && get_time() > AUTO_PAUSE_FRAME_WAIT.as_secs_f64() * 5.0
+        && !game.unradified.is_empty()
+        && !game.is_solved()
+    {
+        debug!("Waited {frame_wait:?} for the window, assuming it's hidden...");
+        game_clock::pause_since(Instant::now() - frame_wait);
+        return true;
+    }
+
+    false
+}
+
 fn update_padding(game: &mut SudokuGame, drawing: &DrawingSettings) -> f32 {
     fn lerp(start: f32, end: f32, t: f32) -> f32 {
         start * (1.0 - t) + end * t
@@ -404,7 +460,7 @@ fn do_auto_play(value: &mut InputAction, x: f32, y: f32, game: &mut SudokuGame)
 }
 
 fn handle_keyboard_movement(game: &mut SudokuGame, key: &Option<InputAction>) {
-    if let Some((sx, sy)) = &mut game.selected_cell {
+    if let Some((mut sx, mut sy)) = game.selected_cell() {
         if let Some(ref key) = *key {
             match key {
                 InputAction::MoveUp => {
@@ -413,15 +469,15 @@ fn handle_keyboard_movement(game: &mut SudokuGame, key: &Option<InputAction>) {
                         InputActionContext::Generic,
                         &game.input,
                     ) {
-                        if *sy < 3 {
-                            *sy += 6;
+                        if sy < 3 {
+                            sy += 6;
                         } else {
-                            *sy -= 3;
+                            sy -= 3;
                         }
-                    } else if *sy == 0 {
-                        *sy = 8;
+                    } else if sy == 0 {
+                        sy = 8;
                     } else {
-                        *sy -= 1;
+                        sy -= 1;
                     }
                 }
                 InputAction::MoveDown => {
@@ -430,15 +486,15 @@ fn handle_keyboard_movement(game: &mut SudokuGame, key: &Option<InputAction>) {
                         InputActionContext::Generic,
                         &game.input,
                     ) {
-                        if *sy > 5 {
-                            *sy -= 6;
+                        if sy > 5 {
+                            sy -= 6;
                         } else {
-                            *sy += 3;
+                            sy += 3;
                         }
-                    } else if *sy == 8 {
-                        *sy = 0;
+                    } else if sy == 8 {
+                        sy = 0;
                     } else {
-                        *sy += 1;
+                        sy += 1;
                     }
                 }
                 InputAction::MoveRight => {
@@ -447,15 +503,15 @@ fn handle_keyboard_movement(game: &mut SudokuGame, key: &Option<InputAction>) {
                         InputActionContext::Generic,
                         &game.input,
                     ) {
-                        if *sx > 5 {
-                            *sx -= 6;
+                        if sx > 5 {
+                            sx -= 6;
                         } else {
-                            *sx += 3;
+                            sx += 3;
                         }
-                    } else if *sx == 8 {
-                        *sx = 0;
+                    } else if sx == 8 {
+                        sx = 0;
                     } else {
-                        *sx += 1;
+                        sx += 1;
                     }
                 }
                 InputAction::MoveLeft => {
@@ -464,19 +520,20 @@ fn handle_keyboard_movement(game: &mut SudokuGame, key: &Option<InputAction>) {
                         InputActionContext::Generic,
                         &game.input,
                     ) {
-                        if *sx < 3 {
-                            *sx += 6;
+                        if sx < 3 {
+                            sx += 6;
                         } else {
-                            *sx -= 3;
+                            sx -= 3;
                         }
-                    } else if *sx == 0 {
-                        *sx = 8;
+                    } else if sx == 0 {
+                        sx = 8;
                     } else {
-                        *sx -= 1;
+                        sx -= 1;
                     }
                 }
-                _ => {}
+                _ => return,
             }
+            game.select((sx, sy));
         }
     }
 }
@@ -490,7 +547,7 @@ fn try_move_selected_from_input(
         let mut change_selected_to_cursor = false;
 
         if let Some(ref value) = *key {
-            if let Some((sx, sy)) = game.selected_cell {
+            if let Some((sx, sy)) = game.selected_cell() {
                 let cell_value = game.cells[(sy as usize, sx as usize)];
                 if cell_value != 0 && matches!(value, InputAction::NumberEntered(_)) {
                     change_selected_to_cursor = true;
@@ -509,9 +566,32 @@ fn try_move_selected_from_input(
             change_selected_to_cursor = false;
         }
 
-        if is_mouse_button_pressed(MouseButton::Left) || change_selected_to_cursor {
-            game.selected_cell = Some((mx, my));
+        // Input is applied to every selected cell, so the selection should stay put
+        if game.selected_cells.len() > 1 {
+            change_selected_to_cursor = false;
         }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if is_key_down(KeyCode::LeftShift) {
+                game.select_range((mx, my));
+            } else if is_key_down(KeyCode::RightControl) {
+                game.toggle_selected((mx, my));
+            } else {
+                game.select((mx, my));
+            }
+            game.input.drag_cell = Some((mx, my));
+        } else if is_mouse_button_down(MouseButton::Left) {
+            if game.input.drag_cell.is_some_and(|cell| cell != (mx, my)) {
+                game.add_to_selection((mx, my));
+                game.input.drag_cell = Some((mx, my));
+            }
+        } else if change_selected_to_cursor {
+            game.select((mx, my));
+        }
+    }
+
+    if !is_mouse_button_down(MouseButton::Left) {
+        game.input.drag_cell = None;
     }
 }
 
@@ -556,7 +636,9 @@ async fn main() {
 
             status_bar.draw(&mut game, &drawing);
             let should_continue = game.reset_signalled != ResetSignal::Hard;
+            let waiting_since = Instant::now();
             next_frame().await;
+            game_clock::set_frame_wait(waiting_since.elapsed());
 
             if !should_continue {
                 warn!("Hard resetting...");
@@ -1,17 +1,9 @@
 use tracing::{span, trace, Level};
 
-use crate::status_bar::eval::Eval;
-use crate::status_bar::find::Find;
 use crate::status_bar::shorthands::list::List;
-use crate::status_bar::{
-    background_image::BackgroundImage, colour_overwrite::ColourOverwrite, font::Font,
-    hard_reset::HardReset, padding::Padding, pencil_marks::PencilMarks,
-};
 use crate::{config, shorthand};
 
-use super::{
-    board_gen::BoardGen, cpu_solve::SolveTask, fps::Fps, on_board_init::OnBoardInit, Item,
-};
+use super::Item;
 
 #[derive(Default)]
 pub struct Add;
@@ -21,9 +13,13 @@ impl Item for Add {
         "BuiltinAdd".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("BuiltinAdd [item_name]...".to_string())
+    }
+
     fn activated(
         &mut self,
-        _game: &mut crate::sudoku_game::SudokuGame,
+        game: &mut crate::sudoku_game::SudokuGame,
         status_bar: &mut super::StatusBar,
     ) {
         let span = span!(Level::INFO, "BuiltinAddActivate");
@@ -42,33 +38,28 @@ impl Item for Add {
             status_bar.buffer
         );
 
+        let buffer = status_bar.buffer.clone();
         let mut count = 0;
-        for item in status_bar.buffer.to_lowercase().split_whitespace() {
+        for item in buffer.split_whitespace() {
             trace!("Adding item: '{}'...", item);
-            match item {
-                "boardgen" => status_bar.add::<BoardGen>(),
-                "onboardinit" => status_bar.add::<OnBoardInit>(),
-                "cpusolve" => status_bar.add::<SolveTask>(),
-                "fps" => status_bar.add::<Fps>(),
-                "colouroverwrite" => status_bar.add::<ColourOverwrite>(),
-                "backgroundimage" => status_bar.add::<BackgroundImage>(),
-                "pencilmarks" => status_bar.add::<PencilMarks>(),
-                "padding" => status_bar.add::<Padding>(),
-                "hardreset" => status_bar.add::<HardReset>(),
-                "find" => status_bar.add::<Find>(),
-                "font" => status_bar.add::<Font>(),
-                "eval" => status_bar.add::<Eval>(),
-                _ => {
-                    status_bar.buffer = "BuiltinAdd: could not find item".to_string();
-                    break;
-                }
-            };
+            if let Err(e) = status_bar.add_item(game, item) {
+                status_bar.buffer = format!("BuiltinAdd: {e}");
+                break;
+            }
             count += 1;
         }
 
         trace!("Added {} item/s to status bar", count);
     }
 
+    fn completions(&self, status_bar: &super::StatusBar, _args: &[&str]) -> Vec<String> {
+        status_bar
+            .registry()
+            .iter()
+            .map(|x| x.name.clone())
+            .collect()
+    }
+
     fn display_mode(&self) -> super::DisplayMode {
         super::DisplayMode::None
     }
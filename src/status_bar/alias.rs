@@ -0,0 +1,99 @@
+use tracing::{debug, span, Level};
+
+use crate::sudoku_game::SudokuGame;
+
+use super::command::split_arguments;
+use super::shorthands::Shorthand;
+use super::{Item, StatusBar};
+
+#[derive(Default)]
+pub struct Alias;
+
+impl Alias {
+    /// Patterns starting with `^` are regular expressions, whilst any other pattern is a prefix,
+    /// with whatever follows it being appended to the expansion
+    fn shorthand(pattern: &str, format: &str) -> Option<Shorthand> {
+        if pattern.starts_with('^') {
+            Shorthand::try_new(pattern, format)
+        } else {
+            Shorthand::try_new(
+                &format!(r"^{}(\s.*)?$", regex_lite::escape(pattern)),
+                &format!("{format}$1"),
+            )
+        }
+    }
+
+    fn list(status_bar: &StatusBar) -> String {
+        let aliases = status_bar
+            .aliases()
+            .map(|(_, x)| format!("{} => {}", x.pattern(), x.format()))
+            .collect::<Vec<_>>();
+
+        if aliases.is_empty() {
+            "No aliases".to_string()
+        } else {
+            aliases.join(", ")
+        }
+    }
+}
+
+impl Item for Alias {
+    fn name(&self) -> String {
+        "Alias".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("Alias ([pattern] [command])?".to_string())
+    }
+
+    fn activated(&mut self, _game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "AliasActivated");
+        let _enter = span.enter();
+
+        match split_arguments(&status_bar.buffer).as_slice() {
+            [] => status_bar.buffer = Self::list(status_bar),
+            [pattern, format] => match Self::shorthand(pattern, format) {
+                Some(alias) => {
+                    debug!("Added alias '{}' => '{}'", pattern, format);
+                    status_bar.add_alias(pattern, alias);
+                }
+                None => status_bar.buffer = "Alias: invalid pattern or format".to_string(),
+            },
+            _ => status_bar.buffer = "Alias: expected a pattern and a command".to_string(),
+        }
+    }
+
+    fn raw_arguments(&self) -> bool {
+        true
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn regex_and_prefix_aliases() {
+        let alias = Alias::shorthand(r"^g(\d+)$", "BoardGen $1 & CpuSolve run").unwrap();
+        assert_eq!(
+            alias.apply_to_string("g25"),
+            Some("BoardGen 25 & CpuSolve run".to_string())
+        );
+        assert_eq!(alias.apply_to_string("g25x"), None);
+
+        let alias = Alias::shorthand("bg", "BoardGen").unwrap();
+        assert_eq!(alias.apply_to_string("bg"), Some("BoardGen".to_string()));
+        assert_eq!(
+            alias.apply_to_string("bg 40"),
+            Some("BoardGen 40".to_string())
+        );
+        assert_eq!(alias.apply_to_string("bgx"), None);
+
+        assert!(Alias::shorthand("^g(", "BoardGen").is_none());
+        assert!(Alias::shorthand(r"^g(\d+)$", "BoardGen $2").is_none());
+    }
+}
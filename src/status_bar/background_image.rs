@@ -28,6 +28,10 @@ impl Item for BackgroundImage {
         "BackgroundImage".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("BackgroundImage ([file_name])|(opacity=[1-255])".to_string())
+    }
+
     fn activated(
         &mut self,
         _game: &mut crate::sudoku_game::SudokuGame,
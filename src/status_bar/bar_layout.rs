@@ -0,0 +1,81 @@
+use tracing::{span, trace, Level};
+
+use crate::draw_helper::StatusBarLayout;
+use crate::sudoku_game::SudokuGame;
+
+use super::{Item, StatusBar};
+
+#[derive(Default)]
+pub struct BarLayout;
+
+impl BarLayout {
+    fn apply(layout: &mut StatusBarLayout, option: &str) -> Result<(), String> {
+        match option.split_once('=') {
+            None => match option {
+                "top" => layout.top = true,
+                "bottom" => layout.top = false,
+                "left" => layout.align_right = false,
+                "right" => layout.align_right = true,
+                _ => Err(format!("unknown option '{option}'"))?,
+            },
+            Some(("rows", rows)) => {
+                layout.max_rows = rows
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|x| *x != 0)
+                    .ok_or_else(|| format!("invalid number of rows '{rows}'"))?;
+            }
+            Some(("collapse", "on")) => layout.collapse = true,
+            Some(("collapse", "off")) => layout.collapse = false,
+            Some(_) => Err(format!("unknown option '{option}'"))?,
+        };
+        Ok(())
+    }
+}
+
+impl Item for BarLayout {
+    fn name(&self) -> String {
+        "StatusBarLayout".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some(
+            "StatusBarLayout [top | bottom | left | right | rows=[n] | collapse=[on | off]]..."
+                .to_string(),
+        )
+    }
+
+    fn activated(&mut self, _game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "StatusBarLayoutActivated");
+        let _enter = span.enter();
+
+        let mut layout = status_bar.drawing.status_bar_layout();
+        for option in status_bar.buffer.clone().split_whitespace() {
+            if let Err(e) = Self::apply(&mut layout, option) {
+                status_bar.buffer = format!("StatusBarLayout: {e}");
+                return;
+            }
+        }
+
+        trace!("Setting status bar layout to {:?}", layout);
+        status_bar.drawing.set_status_bar_layout(layout);
+    }
+
+    fn completions(&self, _status_bar: &StatusBar, _args: &[&str]) -> Vec<String> {
+        [
+            "top",
+            "bottom",
+            "left",
+            "right",
+            "rows=",
+            "collapse=on",
+            "collapse=off",
+        ]
+        .map(ToString::to_string)
+        .to_vec()
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::None
+    }
+}
@@ -139,7 +139,7 @@ impl BoardGen {
                             == solved_game.cells.iter().nth(random_tile_idx).unwrap()
                         {
                             total_numbers -= 1;
-                            if total_numbers % 10 == 0 {
+                            if total_numbers.is_multiple_of(10) {
                                 trace!(
                                     "{}% complete...",
                                     ((f32::from(num_tiles_target) / total_numbers as f32) * 100.0)
@@ -170,6 +170,64 @@ impl BoardGen {
                 .unwrap();
         });
     }
+
+    /// Generates a board with a generator registered by a script, which is then checked for
+    /// conflicts and a unique solution on another thread
+    fn start_generator(&mut self, name: &str, seed: u32, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "Generator");
+        let _enter = span.enter();
+
+        trace!("Running generator '{}' with seed {}...", name, seed);
+        let board = match status_bar.generate_board(name, seed) {
+            Some(Ok(board)) => board,
+            Some(Err(e)) => {
+                error!("Generator '{}' failed: {}", name, e);
+                status_bar.buffer = format!("BoardGen: generator '{name}' failed: {e}");
+                self.status = BoardGenStatus::Failed;
+                return;
+            }
+            None => {
+                error!("Could not find generator '{}'", name);
+                status_bar.buffer = format!("BoardGen: unknown generator '{name}'");
+                self.status = BoardGenStatus::Failed;
+                return;
+            }
+        };
+
+        let Some(cells) = SudokuGame::generate_cells_from_string(&board) else {
+            error!("Generator '{}' returned a malformed board", name);
+            status_bar.buffer = format!("BoardGen: generator '{name}' returned a malformed board");
+            self.status = BoardGenStatus::Failed;
+            return;
+        };
+        self.status = BoardGenStatus::Waiting(cells.iter().filter(|x| **x != 0).count() as u8);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.rx = rx;
+
+        let span = info_span!("Validate");
+        let name = name.to_string();
+        self.thread = std::thread::spawn(move || {
+            let _enter = span.enter();
+
+            let game = SudokuGame::new(Some(&board));
+            let result = if !cpu_solve::get_conflicting_cells(&game).is_empty() {
+                error!("Board from generator '{}' has conflicting cells", name);
+                None
+            } else if cpu_solve::count_solutions(&game, 2) != 1 {
+                error!(
+                    "Board from generator '{}' does not have a unique solution",
+                    name
+                );
+                None
+            } else {
+                debug!("Board from generator '{}' is valid", name);
+                Some(game)
+            };
+
+            let _ = tx.send(BoardGenUpdate::FinalResult(result));
+        });
+    }
 }
 
 impl Default for BoardGen {
@@ -193,6 +251,13 @@ impl Item for BoardGen {
         "BoardGen".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some(
+            "BoardGen ([num_filled_tiles]?)|([flat_board_str])|(gen=[generator] [seed]?)"
+                .to_string(),
+        )
+    }
+
     fn shorthands(&self) -> Option<List> {
         shorthand![(r"^[0-9.]{81}$", "$0")]
     }
@@ -218,6 +283,24 @@ impl Item for BoardGen {
         self.should_stop
             .store(false, std::sync::atomic::Ordering::Relaxed);
 
+        if let Some(args) = status_bar.buffer.clone().strip_prefix("gen=") {
+            let mut args = args.split_whitespace();
+            let name = args.next().unwrap_or_default();
+            let seed = match args.next().map(str::parse::<u32>) {
+                Some(Ok(seed)) => seed,
+                Some(Err(_)) => {
+                    error!("Failed to parse generator seed");
+                    status_bar.buffer = "BoardGen: failed to parse seed".to_string();
+                    self.status = BoardGenStatus::Failed;
+                    return;
+                }
+                None => rand::thread_rng().gen(),
+            };
+
+            self.start_generator(name, seed, status_bar);
+            return;
+        }
+
         trace!(
             "Parsing requested number of remaining tiles: {}...",
             status_bar.buffer
@@ -306,7 +389,7 @@ impl Item for BoardGen {
         self.status = old_status;
     }
 
-    fn status(&mut self) -> super::ItemStatus {
+    fn status(&mut self) -> super::ItemStatus<'_> {
         match self.status {
             BoardGenStatus::NotStarted | BoardGenStatus::Done => {
                 super::ItemStatus::Ok(super::ItemOkData::None)
@@ -27,6 +27,10 @@ impl Item for ColourOverwrite {
         "ColourOverwrite".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("ColourOverwrite [colour_name] #[hex_colour]".to_string())
+    }
+
     fn activated(
         &mut self,
         _game: &mut crate::sudoku_game::SudokuGame,
@@ -62,6 +66,14 @@ impl Item for ColourOverwrite {
             .add_override(app_colour, Color::from_rgba(r, g, b, a));
     }
 
+    fn completions(&self, _status_bar: &super::StatusBar, args: &[&str]) -> Vec<String> {
+        if !args.is_empty() {
+            return Vec::new();
+        }
+
+        AppColour::ALL.iter().map(|x| format!("{x:?}")).collect()
+    }
+
     fn display_mode(&self) -> super::DisplayMode {
         super::DisplayMode::None
     }
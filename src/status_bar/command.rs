@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+/// When a command should run, relative to the command before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    /// Separated by `;`, `&` or a new line, runs without waiting for the previous command
+    Always,
+    /// Separated by `&&`, waits for the previous command and runs if it succeeded
+    OnOk,
+    /// Separated by `||`, waits for the previous command and runs if it failed
+    OnErr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub text: String,
+    pub condition: Condition,
+}
+
+/// Parses text into separate commands. Commands are separated by new lines, `;` and `&` (which
+/// don't wait for the previous command), `&&` and `||`. Separators within double quotes are
+/// ignored, and quotes are kept so that the command can split its own arguments. A `#` at the
+/// start of a word, followed by whitespace, comments out the rest of the line
+pub fn parse_commands(text: &str) -> Vec<Command> {
+    let mut commands = vec![];
+    let mut condition = Condition::Always;
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut comment = false;
+    let mut chars = text.chars().peekable();
+
+    let mut push = |current: &mut String, condition: &mut Condition, next: Condition| {
+        let text = current.trim().to_string();
+        current.clear();
+        if !text.is_empty() {
+            commands.push(Command {
+                text,
+                condition: *condition,
+            });
+            *condition = next;
+        } else if next != Condition::Always {
+            // `a &&` followed by a new line still applies to the next command
+            *condition = next;
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if comment {
+            if c == '\n' {
+                comment = false;
+                push(&mut current, &mut condition, Condition::Always);
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            _ if quoted => current.push(c),
+            '\n' => {
+                push(&mut current, &mut condition, Condition::Always);
+            }
+            ';' => push(&mut current, &mut condition, Condition::Always),
+            '&' if chars.peek() == Some(&'&') => {
+                chars.next();
+                push(&mut current, &mut condition, Condition::OnOk);
+            }
+            '&' => push(&mut current, &mut condition, Condition::Always),
+            '|' if chars.peek() == Some(&'|') => {
+                chars.next();
+                push(&mut current, &mut condition, Condition::OnErr);
+            }
+            '#' if current.chars().last().is_none_or(char::is_whitespace)
+                && chars.peek().is_none_or(|x| x.is_whitespace()) =>
+            {
+                comment = true;
+            }
+            c => current.push(c),
+        }
+    }
+    push(&mut current, &mut condition, Condition::Always);
+
+    commands
+}
+
+/// The byte index at which the last command in `text` starts, after its last separator which isn't
+/// within double quotes
+pub fn last_command_start(text: &str) -> usize {
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' | '&' | '|' | '\n' if !quoted => start = i + c.len_utf8(),
+            _ => {}
+        }
+    }
+    start
+}
+
+/// Splits a command's arguments at whitespace, treating text within double quotes as a single
+/// argument (without the quotes)
+pub fn split_arguments(text: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut quoted = false;
+    let mut current: Option<String> = None;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => args.extend(current.take()),
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    args
+}
+
+/// Whether `name` can be used as a variable, which must start with a letter or underscore, so
+/// that `$1` in aliases is never substituted
+pub fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+        && chars.all(|x| x.is_ascii_alphanumeric() || x == '_')
+}
+
+/// Replaces each `$name` with the value of the variable `name`, and `$$` with `$`. Unknown
+/// variables are left as they are
+pub fn substitute_variables(text: &str, variables: &HashMap<String, String>) -> String {
+    let mut output = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            output.push(c);
+            continue;
+        }
+
+        if chars.peek() == Some(&'$') {
+            chars.next();
+            output.push('$');
+            continue;
+        }
+
+        let mut name = String::new();
+        while let Some(&x) = chars.peek() {
+            if !(x.is_ascii_alphanumeric() || x == '_') {
+                break;
+            }
+            name.push(x);
+            chars.next();
+        }
+
+        match variables.get(&name) {
+            Some(value) if is_variable_name(&name) => output.push_str(value),
+            _ => {
+                output.push('$');
+                output.push_str(&name);
+            }
+        }
+    }
+    output
+}
+
+/// Parses the commands an alias expands into. The first takes the place of the alias command, so it
+/// runs under the alias command's `condition`, and the rest keep their own
+pub fn expand_alias(expanded: &str, condition: Condition) -> Vec<Command> {
+    let mut commands = parse_commands(expanded);
+    if let Some(first) = commands.first_mut() {
+        first.condition = condition;
+    }
+    commands
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn texts(commands: &[Command]) -> Vec<(&str, Condition)> {
+        commands
+            .iter()
+            .map(|x| (&x.text[..], x.condition))
+            .collect()
+    }
+
+    #[test]
+    fn commands_split_outside_quotes() {
+        assert_eq!(
+            texts(&parse_commands("BoardGen 30 & CpuSolve run\n\nFps 60 &")),
+            vec![
+                ("BoardGen 30", Condition::Always),
+                ("CpuSolve run", Condition::Always),
+                ("Fps 60", Condition::Always)
+            ]
+        );
+        assert_eq!(
+            texts(&parse_commands(
+                r#"Alias ^g(\d+)$ "BoardGen $1 && CpuSolve run; x" ; Fps"#
+            )),
+            vec![
+                (
+                    r#"Alias ^g(\d+)$ "BoardGen $1 && CpuSolve run; x""#,
+                    Condition::Always
+                ),
+                ("Fps", Condition::Always)
+            ]
+        );
+    }
+
+    #[test]
+    fn commands_have_conditions_and_comments() {
+        assert_eq!(
+            texts(&parse_commands(
+                "# Generate a board\nBoardGen 30 && # then solve it\n  CpuSolve run || Fps 1\nColourOverwrite Background #000000 # black"
+            )),
+            vec![
+                ("BoardGen 30", Condition::Always),
+                ("CpuSolve run", Condition::OnOk),
+                ("Fps 1", Condition::OnErr),
+                ("ColourOverwrite Background #000000", Condition::Always)
+            ]
+        );
+    }
+
+    #[test]
+    fn alias_expansions_take_the_alias_condition() {
+        let mut commands = parse_commands("a && g || b");
+        let expanded = expand_alias("x; y && z", commands[1].condition);
+        commands.splice(1..2, expanded);
+        assert_eq!(
+            texts(&commands),
+            vec![
+                ("a", Condition::Always),
+                ("x", Condition::OnOk),
+                ("y", Condition::Always),
+                ("z", Condition::OnOk),
+                ("b", Condition::OnErr)
+            ]
+        );
+    }
+
+    #[test]
+    fn last_command_starts_after_separator() {
+        assert_eq!(last_command_start("Fps"), 0);
+        assert_eq!(last_command_start("BoardGen 30 && Cpu"), 14);
+        assert_eq!(last_command_start(r#"Alias a "b; c"#), 0);
+    }
+
+    #[test]
+    fn arguments_split_outside_quotes() {
+        assert_eq!(
+            split_arguments(r#" ^g(\d+)$  "BoardGen $1 & CpuSolve run" "" x"#),
+            vec![r"^g(\d+)$", "BoardGen $1 & CpuSolve run", "", "x"]
+        );
+        assert_eq!(split_arguments("a b\"c d\""), vec!["a", "bc d"]);
+    }
+
+    #[test]
+    fn variables_are_substituted() {
+        let variables = HashMap::from([
+            ("n".to_string(), "25".to_string()),
+            ("1".to_string(), "x".to_string()),
+        ]);
+        assert_eq!(
+            substitute_variables("BoardGen $n $1 $$n $unknown $", &variables),
+            "BoardGen 25 $1 $n $unknown $"
+        );
+    }
+}
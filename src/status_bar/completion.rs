@@ -0,0 +1,76 @@
+use crate::config;
+
+/// Candidates which were offered for the same word, so that pressing Tab again moves on to the
+/// next one
+pub struct Completion {
+    pub before: String,
+    pub candidates: Vec<String>,
+    pub index: usize,
+}
+
+impl Completion {
+    pub fn current(&self) -> String {
+        format!("{}{}", self.before, self.candidates[self.index])
+    }
+}
+
+/// Sorted candidates which start with `word`, ignoring case
+pub fn matching(word: &str, candidates: Vec<String>) -> Vec<String> {
+    let word = word.to_lowercase();
+    let mut candidates = candidates
+        .into_iter()
+        .filter(|x| x.to_lowercase().starts_with(&word))
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|x| x.to_lowercase());
+    candidates.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    candidates
+}
+
+/// The longest prefix shared by all candidates, ignoring case, in the case of the first candidate
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some((first, rest)) = candidates.split_first() else {
+        return String::new();
+    };
+
+    first
+        .chars()
+        .enumerate()
+        .take_while(|(i, c)| {
+            rest.iter()
+                .all(|x| x.chars().nth(*i).is_some_and(|x| x.eq_ignore_ascii_case(c)))
+        })
+        .map(|(_, c)| c)
+        .collect()
+}
+
+/// Names of the Lua scripts in the config directory
+pub fn script_files() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(config::get_file_path("")) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|x| x.file_name().to_string_lossy().to_string())
+        .filter(|x| x.ends_with(".lua"))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn candidates_match_and_share_prefix() {
+        let candidates = matching(
+            "bo",
+            ["BoardGen", "Fps", "BoardBox", "boardgen", "BackgroundImage"]
+                .map(ToString::to_string)
+                .to_vec(),
+        );
+        assert_eq!(candidates, vec!["BoardBox", "BoardGen"]);
+        assert_eq!(common_prefix(&candidates), "Board");
+        assert_eq!(common_prefix(&candidates[..1]), "BoardBox");
+        assert_eq!(common_prefix(&[]), "");
+    }
+}
@@ -48,6 +48,16 @@ impl SolveTask {
         }
     }
 
+    /// Blocks until the solve finishes, if it hasn't already
+    pub fn wait(&mut self) {
+        if let TaskStatus::Waiting(_) = self.status {
+            self.status = match self.rx.recv() {
+                Ok(Some(game)) => TaskStatus::<SudokuGame>::Done(Box::new(game)),
+                _ => TaskStatus::<SudokuGame>::Failed,
+            };
+        }
+    }
+
     pub fn get(&self) -> &TaskStatus<SudokuGame> {
         &self.status
     }
@@ -69,6 +79,10 @@ impl Item for SolveTask {
         "CpuSolve".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("CpuSolve (run)?".to_string())
+    }
+
     fn update(
         &mut self,
         _game: &mut SudokuGame,
@@ -117,7 +131,7 @@ impl Item for SolveTask {
         }
     }
 
-    fn status(&mut self) -> ItemStatus {
+    fn status(&mut self) -> ItemStatus<'_> {
         self.update_status();
         match self.get() {
             TaskStatus::Done(game) => ItemStatus::Ok(ItemOkData::Game(game.as_ref())),
@@ -155,6 +169,69 @@ pub fn solve(game: &SudokuGame) -> Option<SudokuGame> {
     solve_inner(&mut game, 0, &pool, 0)
 }
 
+/// Counts the solutions of `game`, stopping once `limit` have been found. Unlike `solve`, this
+/// runs on the calling thread, and always branches on the cell with the fewest candidates
+pub fn count_solutions(game: &SudokuGame, limit: usize) -> usize {
+    fn count_inner(game: &mut SudokuGame, limit: usize, count: &mut usize) {
+        let size = game.cells.shape()[1] as u32;
+        let mut best: Option<((usize, usize), BitVec, usize)> = None;
+        for idx in 0..game.cells.len() as u32 {
+            let (sx, sy) = SudokuGame::idx_pos_to_xy(idx, size);
+            if game.cells[(sy as usize, sx as usize)] != 0 {
+                continue;
+            }
+
+            let occupied = get_occupied_numbers_at_cell(game, (sx, sy));
+            let free = occupied.iter().filter(|x| !x).count();
+            if best.as_ref().is_none_or(|(_, _, x)| free < *x) {
+                best = Some(((sy as usize, sx as usize), occupied, free));
+                if free == 0 {
+                    break;
+                }
+            }
+        }
+
+        let Some((pos, occupied, _)) = best else {
+            *count += 1;
+            return;
+        };
+
+        for num in 1..=9 {
+            if occupied[num - 1] {
+                continue;
+            }
+            game.cells[pos] = num as u8;
+            count_inner(game, limit, count);
+            if *count >= limit {
+                break;
+            }
+        }
+        game.cells[pos] = 0;
+    }
+
+    let mut game = game.clone();
+    let mut count = 0;
+    count_inner(&mut game, limit, &mut count);
+    count
+}
+
+/// Indexes of the filled cells which share their number with another cell in sight
+pub fn get_conflicting_cells(game: &SudokuGame) -> Vec<u32> {
+    let size = game.cells.shape()[1] as u32;
+    let cells = game.cells.iter().copied().collect::<Vec<_>>();
+
+    (0..cells.len() as u32)
+        .filter(|idx| {
+            let value = cells[*idx as usize];
+            value != 0
+                && game
+                    .get_cells_in_sight(SudokuGame::idx_pos_to_xy(*idx, size))
+                    .iter()
+                    .any(|x| x != idx && cells[*x as usize] == value)
+        })
+        .collect()
+}
+
 pub fn get_cells_in_box(game: &SudokuGame, box_pos: (u32, u32)) -> Vec<u32> {
     let size = game.cells.shape()[1];
     let (start_x, start_y) = (box_pos.0 * 3, box_pos.1 * 3);
@@ -362,3 +439,35 @@ pub(super) fn get_occupied_numbers_at_cell(game: &SudokuGame, cell_pos: (u32, u3
 
     vec
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BOARD: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn counts_solutions() {
+        let game = SudokuGame::new(Some(BOARD));
+        assert_eq!(count_solutions(&game, 2), 1);
+
+        let ambiguous = format!("{}{}", &BOARD[..27], "0".repeat(54));
+        let game = SudokuGame::new(Some(&ambiguous));
+        assert_eq!(count_solutions(&game, 2), 2);
+
+        let empty = SudokuGame::new(None);
+        assert_eq!(count_solutions(&empty, 3), 3);
+    }
+
+    #[test]
+    fn finds_conflicting_cells() {
+        let game = SudokuGame::new(Some(BOARD));
+        assert!(get_conflicting_cells(&game).is_empty());
+
+        let conflicting = BOARD.replacen("530", "535", 1);
+        let game = SudokuGame::new(Some(&conflicting));
+        assert_eq!(get_conflicting_cells(&game), vec![0, 2]);
+        assert_eq!(count_solutions(&game, 2), 0);
+    }
+}
@@ -1,24 +1,54 @@
 #![allow(clippy::similar_names)]
+use std::cell::{RefCell, RefMut};
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
 use std::default::Default;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
 
 use macroquad::color::Color;
-use macroquad::input::{is_mouse_button_down, mouse_position, MouseButton};
+use macroquad::input::{
+    get_keys_down, get_keys_pressed, is_mouse_button_down, is_mouse_button_released,
+    mouse_position, mouse_wheel, MouseButton,
+};
+use macroquad::math::Vec2;
 use macroquad::miniquad::window::screen_size;
+use macroquad::miniquad::KeyCode;
 use macroquad::prelude::is_mouse_button_pressed;
-use macroquad::shapes::draw_rectangle;
-use macroquad::window::{screen_height, screen_width};
-use mlua::prelude::{LuaResult, LuaUserData, LuaUserDataMethods};
+use macroquad::shapes::{draw_circle, draw_line, draw_poly, draw_rectangle, draw_rectangle_lines};
+use macroquad::texture::{draw_texture_ex, DrawTextureParams, Image, Texture2D};
+use macroquad::window::screen_width;
+use mlua::prelude::{LuaResult, LuaUserData, LuaUserDataMethods, LuaUserDataRef};
 use mlua::Error::RuntimeError;
-use mlua::{FromLuaMulti, Function, Lua, LuaOptions, StdLib, Table, Value};
+use mlua::{
+    AnyUserData, FromLua, FromLuaMulti, Function, IntoLuaMulti, Lua, LuaOptions, MultiValue,
+    StdLib, Table, Value, VmState,
+};
 use tracing::{debug, error, info, info_span, span, trace, warn, Level};
 
 use crate::draw_helper::{draw_text_in_bounds, get_status_bar_height, DrawingSettings};
+use crate::input_helper::{InputAction, InputActionContext};
 use crate::status_bar::shorthands::list::List;
+use crate::storage::{Store, StoredValue};
 use crate::sudoku_game::SudokuGame;
-use crate::{config, AppColour};
-
-use super::{cpu_solve, Item, ItemOkData, ItemStatus, StatusBar};
+use crate::task_status::TaskStatus;
+use crate::{config, game_clock, AppColour};
+
+use super::registry::{ItemFactory, ItemSource};
+use super::{
+    completion, cpu_solve, plugins, scripts, stats, DrawHookData, HookAction, Item, ItemOkData,
+    ItemStatus, StatusBar,
+};
+
+const MAX_STORED_TABLE_DEPTH: usize = 32;
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_PRETTY_PRINT_DEPTH: usize = 4;
+const MAX_PRETTY_PRINT_ENTRIES: usize = 32;
+const DEFAULT_REPL_SESSION: &str = "default";
+const CALLBACK_TIME_BUDGET: Duration = Duration::from_millis(250);
+const SCRIPT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
 
 impl LuaUserData for SudokuGame {
     fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
@@ -36,22 +66,71 @@ impl LuaUserData for SudokuGame {
         });
 
         methods.add_method::<_, u32, _>("unoccupied_cells_at", |_, s, idx| {
-            let in_sight = cpu_solve::get_occupied_numbers_at_cell(
-                s,
-                SudokuGame::idx_pos_to_xy(idx, s.cells.shape()[1] as u32),
-            );
-            let mut not_in_sight = vec![];
-            for i in 1..=9 {
-                if !in_sight[i - 1] {
-                    not_in_sight.push(i);
-                }
+            Ok(unoccupied_numbers_at(s, cell_index(s, idx)?))
+        });
+        methods.add_method("candidates", |_, s, ()| {
+            Ok((0..s.cells.len() as u32)
+                .map(|idx| {
+                    if s.cells.iter().nth(idx as usize) == Some(&0) {
+                        unoccupied_numbers_at(s, idx)
+                    } else {
+                        vec![]
+                    }
+                })
+                .collect::<Vec<_>>())
+        });
+        methods.add_method::<_, u32, _>("peers", |_, s, idx| Ok(peers_of(s, cell_index(s, idx)?)));
+        methods.add_method::<_, u32, _>("row", |_, s, i| {
+            Ok(cpu_solve::get_cells_in_row(s, unit_index(i)?))
+        });
+        methods.add_method::<_, u32, _>("col", |_, s, i| {
+            Ok(cpu_solve::get_cells_in_col(s, unit_index(i)?))
+        });
+        methods.add_method::<_, u32, _>("box", |_, s, i| {
+            let i = unit_index(i)?;
+            let mut cells = cpu_solve::get_cells_in_box(s, (i % 3, i / 3));
+            cells.sort_unstable();
+            Ok(cells)
+        });
+        methods.add_method_mut::<_, (u32, u8), ()>("set_cell", |_, s, (idx, value)| {
+            let idx = cell_index(s, idx)?;
+            if value > 9 {
+                return Err(RuntimeError("Cell value out of range".to_string()));
             }
-
-            Ok(not_in_sight)
+            if !s.unradified.contains(&(idx as u8)) {
+                return Err(RuntimeError("Cannot change a given cell".to_string()));
+            }
+            let (x, y) = SudokuGame::idx_pos_to_xy(idx, s.cells.shape()[1] as u32);
+            s.cells[(y as usize, x as usize)] = value;
+            Ok(())
+        });
+        methods.add_method::<_, u32, _>("is_given", |_, s, idx| {
+            let idx = cell_index(s, idx)?;
+            Ok(!s.unradified.contains(&(idx as u8)))
+        });
+        methods.add_method("conflicts", |_, s, ()| {
+            Ok(cpu_solve::get_conflicting_cells(s))
         });
 
         methods.add_method("board_string", |_, s, ()| Ok(s.board_string()));
-        methods.add_method("solve", |_, s, ()| Ok(cpu_solve::solve(s)));
+        methods.add_method("solve", |lua, s, ()| {
+            Ok(outside_budget(lua, || cpu_solve::solve(s)))
+        });
+        methods.add_method::<_, Function, ()>("solve_async", |lua, s, callback| {
+            let Some(mut solves) = lua.app_data_mut::<PendingSolves>() else {
+                return Err(RuntimeError(
+                    "solve_async can only be used by script files".to_string(),
+                ));
+            };
+            let id = solves.next_id;
+            solves.next_id += 1;
+            solves.tasks.insert(id, cpu_solve::SolveTask::new(s));
+            drop(solves);
+
+            lua.globals()
+                .get::<_, Table>("__PENDING_SOLVES__")?
+                .raw_set(id, callback)
+        });
         methods.add_method("is_solved", |_, s, ()| Ok(s.is_solved()));
         methods.add_method_mut::<_, String, ()>("update_board_from_string", |_, s, inp| {
             let Some(grid) = SudokuGame::generate_cells_from_string(&inp) else {
@@ -65,6 +144,34 @@ impl LuaUserData for SudokuGame {
             s.reset(new_game);
             Ok(())
         });
+        methods.add_method("selection", |_, s, ()| {
+            let size = s.cells.shape()[1] as u32;
+            Ok(s.selected_cells
+                .iter()
+                .map(|(x, y)| SudokuGame::xy_pos_to_idx(*x, *y, size))
+                .collect::<Vec<_>>())
+        });
+        methods.add_method_mut::<_, Vec<u32>, ()>("set_selection", |_, s, inp| {
+            let size = s.cells.shape()[1] as u32;
+            if inp.iter().any(|idx| *idx as usize >= s.cells.len()) {
+                return Err(RuntimeError("Cell index out of range".to_string()));
+            }
+            s.set_selection(
+                inp.into_iter()
+                    .map(|idx| SudokuGame::idx_pos_to_xy(idx, size)),
+            );
+            Ok(())
+        });
+        methods.add_method("marks", |_, s, ()| Ok(s.marks.clone()));
+        methods.add_method_mut::<_, (u32, u8), ()>("mark", |_, s, (idx, colour)| {
+            if idx as usize >= s.marks.len() || colour > 9 {
+                return Err(RuntimeError(
+                    "Invalid cell index or mark colour".to_string(),
+                ));
+            }
+            s.marks[idx as usize] = colour;
+            Ok(())
+        });
         methods.add_method_mut::<_, String, ()>("enter_buffer_command", |_, s, inp| {
             s.wanted_commands.push(inp);
             Ok(())
@@ -72,23 +179,382 @@ impl LuaUserData for SudokuGame {
     }
 }
 
+/// Runs `f` with `sudoku` bound as scoped userdata, along with the command functions, which need
+/// mutable access to the status bar and so are only valid for the duration of the scope
+fn game_scope<'lua, R>(
+    lua: &'lua Lua,
+    sudoku: &mut SudokuGame,
+    status_bar: &mut StatusBar,
+    f: impl FnOnce(AnyUserData<'lua>) -> LuaResult<R>,
+) -> LuaResult<R> {
+    let status_bar = RefCell::new(status_bar);
+    with_budget(lua, || {
+        lua.scope(|scope| {
+            let bs = scope.create_userdata_ref_mut(sudoku)?;
+            lua.globals().set("__SCOPED_GAME__", bs.clone())?;
+
+            lua.globals().set(
+                "__RUN_COMMAND__",
+                scope.create_function(|lua, cmd: String| {
+                    let game = lua.globals().get::<_, AnyUserData>("__SCOPED_GAME__")?;
+                    let mut game = game.borrow_mut::<SudokuGame>()?;
+                    let mut status_bar = borrow_status_bar(&status_bar)?;
+                    let result = outside_budget(lua, || status_bar.run_command(&mut game, &cmd));
+                    Ok((result.status, result.buffer))
+                })?,
+            )?;
+
+            lua.globals().set(
+                "__QUEUE_COMMAND__",
+                scope.create_function(|_, cmd: String| {
+                    Ok(borrow_status_bar(&status_bar)?.enter_tracked_buffer_command(&cmd))
+                })?,
+            )?;
+
+            lua.globals().set(
+                "__STATUS_BAR_ITEMS__",
+                scope.create_function(|lua, ()| {
+                    let items = lua.create_table()?;
+                    for summary in borrow_status_bar(&status_bar)?.item_summaries() {
+                        let item = lua.create_table()?;
+                        item.set("name", summary.name)?;
+                        item.set("display_mode", summary.display_mode.to_string())?;
+                        item.set("status", summary.status)?;
+                        items.push(item)?;
+                    }
+                    Ok(items)
+                })?,
+            )?;
+
+            lua.globals().set(
+                "__STATUS_BAR_REMOVE__",
+                scope.create_function(|_, name: String| {
+                    borrow_status_bar(&status_bar)?
+                        .remove_item(&name)
+                        .map_err(RuntimeError)
+                })?,
+            )?;
+
+            lua.globals().set(
+                "__STATUS_BAR_MOVE__",
+                scope.create_function(|_, (name, index): (String, usize)| {
+                    borrow_status_bar(&status_bar)?
+                        .move_item(&name, index)
+                        .map_err(RuntimeError)
+                })?,
+            )?;
+
+            lua.globals().set(
+                "__STATUS_BAR_REGISTER__",
+                scope.create_function(|_, item: Table| {
+                    let factory = ItemFactory {
+                        name: item.get("name")?,
+                        description: item.get::<_, Option<_>>("description")?.unwrap_or_default(),
+                        usage: item.get::<_, Option<_>>("usage")?.unwrap_or_default(),
+                        singleton: item.get::<_, Option<_>>("singleton")?.unwrap_or(false),
+                        source: ItemSource::Command(item.get("command")?),
+                    };
+
+                    borrow_status_bar(&status_bar)?
+                        .registry_mut()
+                        .register(factory)
+                        .map_err(RuntimeError)
+                })?,
+            )?;
+
+            lua.globals().set(
+                "__STATUS_BAR_BUFFER__",
+                scope
+                    .create_function(|_, ()| Ok(borrow_status_bar(&status_bar)?.buffer.clone()))?,
+            )?;
+
+            lua.globals().set(
+                "__STATUS_BAR_SET_BUFFER__",
+                scope.create_function(|_, buffer: String| {
+                    borrow_status_bar(&status_bar)?.buffer = buffer;
+                    Ok(())
+                })?,
+            )?;
+
+            f(bs)
+        })
+    })
+}
+
+#[derive(Default)]
+struct CallBudget {
+    deadline: Option<Instant>,
+    exceeded: bool,
+}
+
+/// Limits the memory of `lua`, and aborts any callback which runs for longer than its time budget
+/// (see `with_budget`), so that a broken script can't freeze the game
+fn limit_lua(lua: &Lua) -> LuaResult<()> {
+    lua.set_app_data(CallBudget::default());
+    lua.set_memory_limit(SCRIPT_MEMORY_LIMIT)?;
+    lua.set_interrupt(|lua| {
+        if let Some(mut budget) = lua.app_data_mut::<CallBudget>() {
+            if budget.deadline.is_some_and(|x| Instant::now() > x) {
+                budget.exceeded = true;
+                return Err(RuntimeError(format!(
+                    "Callback exceeded its time budget of {}ms",
+                    CALLBACK_TIME_BUDGET.as_millis()
+                )));
+            }
+        }
+        Ok(VmState::Continue)
+    });
+    Ok(())
+}
+
+/// Runs `f` with a time budget, unless it is nested within a call which already has one
+fn with_budget<R>(lua: &Lua, f: impl FnOnce() -> R) -> R {
+    let started = lua.app_data_mut::<CallBudget>().is_some_and(|mut x| {
+        if x.deadline.is_some() {
+            return false;
+        }
+        x.deadline = Some(Instant::now() + CALLBACK_TIME_BUDGET);
+        true
+    });
+
+    let result = f();
+
+    if started {
+        if let Some(mut budget) = lua.app_data_mut::<CallBudget>() {
+            budget.deadline = None;
+        }
+    }
+    result
+}
+
+/// Runs native code which can be slow, such as solving the board, without counting the time it
+/// takes against the budget of the callback which called it
+fn outside_budget<R>(lua: &Lua, f: impl FnOnce() -> R) -> R {
+    let started = Instant::now();
+    let result = f();
+
+    if let Some(mut budget) = lua.app_data_mut::<CallBudget>() {
+        if let Some(deadline) = &mut budget.deadline {
+            *deadline += started.elapsed();
+        }
+    }
+    result
+}
+
+fn is_memory_error(error: &mlua::Error) -> bool {
+    match error {
+        mlua::Error::MemoryError(_) => true,
+        mlua::Error::CallbackError { cause, .. } => is_memory_error(cause),
+        _ => false,
+    }
+}
+
+/// A texture loaded by `drawing.load_image`
+struct LuaImage(Texture2D);
+
+/// Textures which have been loaded by a script, so that loading the same image again doesn't
+/// upload another copy of it
+#[derive(Default)]
+struct ImageCache(BTreeMap<PathBuf, Texture2D>);
+
+impl LuaUserData for LuaImage {
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("size", |_, s, ()| Ok((s.0.width(), s.0.height())));
+    }
+}
+
+/// Solves started with `Game:solve_async`, which run on their own thread like `CpuSolve` does
+#[derive(Default)]
+struct PendingSolves {
+    next_id: u64,
+    tasks: BTreeMap<u64, cpu_solve::SolveTask>,
+}
+
+fn cell_index(game: &SudokuGame, idx: u32) -> LuaResult<u32> {
+    if idx as usize >= game.cells.len() {
+        return Err(RuntimeError("Cell index out of range".to_string()));
+    }
+    Ok(idx)
+}
+
+/// Checks the index of a row, column or box
+fn unit_index(i: u32) -> LuaResult<u32> {
+    if i >= 9 {
+        return Err(RuntimeError(
+            "Row, column or box index out of range".to_string(),
+        ));
+    }
+    Ok(i)
+}
+
+fn unoccupied_numbers_at(game: &SudokuGame, idx: u32) -> Vec<usize> {
+    let in_sight = cpu_solve::get_occupied_numbers_at_cell(
+        game,
+        SudokuGame::idx_pos_to_xy(idx, game.cells.shape()[1] as u32),
+    );
+    (1..=9).filter(|i| !in_sight[i - 1]).collect()
+}
+
+/// The cells sharing a row, column or box with `idx`, excluding itself
+fn peers_of(game: &SudokuGame, idx: u32) -> Vec<u32> {
+    let mut peers =
+        game.get_cells_in_sight(SudokuGame::idx_pos_to_xy(idx, game.cells.shape()[1] as u32));
+    peers.sort_unstable();
+    peers.dedup();
+    peers.retain(|x| *x != idx);
+    peers
+}
+
+/// Parses the name of a mouse button, defaulting to the left button
+fn mouse_button(name: Option<String>) -> LuaResult<MouseButton> {
+    match name.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("left") => Ok(MouseButton::Left),
+        Some("right") => Ok(MouseButton::Right),
+        Some("middle") => Ok(MouseButton::Middle),
+        Some(x) => Err(RuntimeError(format!("Unknown mouse button: {x}"))),
+    }
+}
+
+/// Key names are the same as the `KeyCode` variants, ignoring case (`A`, `Key1`, `Space`, `LeftShift`)
+fn key_matches(key: KeyCode, name: &str) -> bool {
+    format!("{key:?}").eq_ignore_ascii_case(name)
+}
+
+/// Converts a Lua value into one which can be stored, where `nil` has no stored representation.
+/// Nesting is limited, which also prevents tables which contain themselves from being stored
+fn to_stored_value(value: Value, depth: usize) -> LuaResult<Option<StoredValue>> {
+    Ok(Some(match value {
+        Value::Nil => return Ok(None),
+        Value::Boolean(x) => StoredValue::Boolean(x),
+        Value::Integer(x) => StoredValue::Integer(i64::from(x)),
+        Value::Number(x) => StoredValue::Number(x),
+        Value::String(x) => StoredValue::String(x.to_str()?.to_string()),
+        Value::Table(table) => {
+            if depth >= MAX_STORED_TABLE_DEPTH {
+                Err(RuntimeError(
+                    "Table is too deeply nested to store (or contains itself)".to_string(),
+                ))?;
+            }
+
+            let mut entries = vec![];
+            for pair in table.pairs::<Value, Value>() {
+                let (key, value) = pair?;
+                if let (Some(key), Some(value)) = (
+                    to_stored_value(key, depth + 1)?,
+                    to_stored_value(value, depth + 1)?,
+                ) {
+                    entries.push((key, value));
+                }
+            }
+            StoredValue::Table(entries)
+        }
+        x => Err(RuntimeError(format!(
+            "Values of type '{}' cannot be stored",
+            x.type_name()
+        )))?,
+    }))
+}
+
+fn from_stored_value<'lua>(lua: &'lua Lua, value: &StoredValue) -> LuaResult<Value<'lua>> {
+    Ok(match value {
+        StoredValue::Boolean(x) => Value::Boolean(*x),
+        StoredValue::Integer(x) => Value::Number(*x as f64),
+        StoredValue::Number(x) => Value::Number(*x),
+        StoredValue::String(x) => Value::String(lua.create_string(x)?),
+        StoredValue::Table(entries) => {
+            let table = lua.create_table()?;
+            for (key, value) in entries {
+                table.set(from_stored_value(lua, key)?, from_stored_value(lua, value)?)?;
+            }
+            Value::Table(table)
+        }
+    })
+}
+
+fn borrow_status_bar<'a, 'b, 'c>(
+    status_bar: &'a RefCell<&'b mut StatusBar<'c>>,
+) -> LuaResult<RefMut<'a, &'b mut StatusBar<'c>>> {
+    status_bar
+        .try_borrow_mut()
+        .map_err(|_| RuntimeError("Status bar is already in use".to_string()))
+}
+
 #[derive(Default)]
-pub struct Eval;
+pub struct Eval {
+    sessions: BTreeMap<String, ReplSession>,
+}
 
-enum LuaRun {
+enum LuaRun<'a> {
     File {
         code: String,
         allow_duplicate: bool,
+        watch: bool,
         name: String,
     },
     Repl {
         code: String,
+        session: &'a mut ReplSession,
     },
 }
 
+/// A `Repl` session, whose globals persist between evaluations
+struct ReplSession {
+    name: String,
+    lua: Lua,
+    /// An incomplete chunk, which is prepended to the next line entered
+    pending: String,
+    history: Vec<String>,
+    history_offset: usize,
+}
+
+/// Tracks the modification time of a script file, so that it can be reloaded when it changes
+struct ScriptWatch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl ScriptWatch {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            modified: Self::modified(&path),
+            path,
+            last_poll: Instant::now(),
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|x| x.modified()).ok()
+    }
+
+    /// Returns true if the file has been modified since the last poll
+    fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < WATCH_POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        let modified = Self::modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+        modified.is_some()
+    }
+}
+
 struct LuaScript {
+    id: u64,
     name: String,
     lua: Lua,
+    watch: Option<ScriptWatch>,
+}
+
+impl Drop for LuaScript {
+    fn drop(&mut self) {
+        scripts::unregister(self.id);
+    }
 }
 
 impl Item for LuaScript {
@@ -97,38 +563,82 @@ impl Item for LuaScript {
             .unwrap_or_else(|_| self.name.clone())
     }
 
+    fn help(&self) -> Option<String> {
+        self.generic_single_callback::<String>(None, "__ON_HELP_FUNCTION")
+            .ok()
+    }
+
     fn update(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar) -> (String, Color) {
-        if let Err(e) = self.generic_game_callback(game, "__ON_UPDATE_FUNCTIONS__") {
-            error!("Lua 'Update' error: {e}");
+        // Storage changes made since the last frame are written once, however many there were
+        self.flush_storage();
+
+        if self.watch.as_mut().is_some_and(ScriptWatch::poll) {
+            info!("Script '{}' changed, reloading...", self.name);
+            if let Err(e) = self.reload(game, status_bar) {
+                self.report_error("Reload", &e);
+            }
+        }
+
+        if scripts::is_disabled(self.id) {
+            return (
+                "disabled".to_string(),
+                status_bar.drawing.colour(AppColour::StatusBarItemError),
+            );
+        }
+
+        if let Err(e) = self.generic_game_callback(game, status_bar, "__ON_UPDATE_FUNCTIONS__") {
+            self.report_error("Update", &e);
+        }
+
+        if let Err(e) = self.update_wait_funcs(game, status_bar) {
+            self.report_error("WaitMS", &e);
         }
 
-        if let Err(e) = self.update_wait_funcs(game) {
-            error!("Lua 'WaitMS' error: {e}");
+        if let Err(e) = self.update_pending_commands(game, status_bar) {
+            self.report_error("RunAsync", &e);
+        }
+
+        if let Err(e) = self.update_pending_solves(game, status_bar) {
+            self.report_error("SolveAsync", &e);
         }
 
         game.flush_wanted_commands(status_bar);
 
-        let (text, colour) = self
-            .generic_single_callback::<(String, String)>(Some(game), "__ON_UPDATE_FUNCTION")
+        let (mut text, colour) = self
+            .generic_single_callback::<(String, String)>(
+                Some((game, status_bar)),
+                "__ON_UPDATE_FUNCTION",
+            )
             .unwrap_or_else(|e| {
-                error!("Lua 'Update' error: {e}");
+                self.report_error("Update", &e);
                 (String::new(), "StatusBarItemOkay".to_string())
             });
-        (
-            text,
-            status_bar
-                .drawing
-                .colour(AppColour::from_str(&colour).unwrap_or_else(|()| {
-                    warn!(
-                        "Script {} attempted to use invalid status bar colour: {}",
-                        self.name, colour
-                    );
-                    AppColour::StatusBarItemError
-                })),
-        )
+
+        let mut colour = AppColour::from_str(&colour).unwrap_or_else(|()| {
+            self.report_error(
+                "Update",
+                &RuntimeError(format!("invalid status bar colour '{colour}'")),
+            );
+            AppColour::StatusBarItemError
+        });
+
+        if let Some(badge) = scripts::error_badge(self.id) {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&badge);
+            colour = AppColour::StatusBarItemError;
+        }
+
+        (text, status_bar.drawing.colour(colour))
     }
 
     fn activated(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        if scripts::is_disabled(self.id) {
+            status_bar.buffer = format!("{}: disabled after too many errors", self.name);
+            return;
+        }
+
         let status_bar_content = status_bar.buffer.clone();
         let Ok(func) = self
             .lua
@@ -138,19 +648,22 @@ impl Item for LuaScript {
             error!("Lua '{}' error on activated (not found)", self.name);
             return;
         };
-        if let Err(e) = self.lua.scope(|scope| {
-            let bs = scope.create_userdata_ref_mut(game).unwrap();
+        if let Err(e) = game_scope(&self.lua, game, status_bar, |bs| {
             func.call::<_, ()>((bs, status_bar_content))
         }) {
-            error!("Lua '{}' error: {}", self.name, e);
+            self.report_error("Activate", &e);
         }
     }
-    fn board_init(&mut self, game: &mut SudokuGame, _status_bar: &mut StatusBar) {
+    fn board_init(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar) {
         let span = span!(Level::INFO, "RunLua");
         let _enter = span.enter();
 
-        if let Err(e) = self.generic_game_callback(game, "__ON_BOARDGEN_FUNCTIONS__") {
-            error!("Lua 'BoardInit' error: {e}");
+        if scripts::is_disabled(self.id) {
+            return;
+        }
+
+        if let Err(e) = self.generic_game_callback(game, status_bar, "__ON_BOARDGEN_FUNCTIONS__") {
+            self.report_error("BoardInit", &e);
         }
     }
 
@@ -166,38 +679,252 @@ impl Item for LuaScript {
             "NameOnly" => super::DisplayMode::NameOnly,
             "StatusOnly" => super::DisplayMode::StatusOnly,
             "None" => super::DisplayMode::None,
-            _ => panic!(
-                "Invalid display mode '{}' requested by script '{}'",
-                display_mode, self.name
-            ),
+            _ => {
+                self.report_error(
+                    "DisplayMode",
+                    &RuntimeError(format!("invalid display mode '{display_mode}'")),
+                );
+                super::DisplayMode::Normal
+            }
         }
     }
 
-    fn status(&mut self) -> super::ItemStatus {
+    fn status(&mut self) -> super::ItemStatus<'_> {
         super::ItemStatus::Ok(ItemOkData::LuaScript(self.name.clone()))
     }
+
+    fn key_hook(
+        &mut self,
+        game: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+        key: KeyCode,
+    ) -> HookAction<()> {
+        if scripts::is_disabled(self.id) {
+            return HookAction::Continue(());
+        }
+
+        let result = self
+            .lua
+            .globals()
+            .get::<_, Table>("__ON_KEY_FUNCTIONS__")
+            .and_then(|funcs| {
+                let mut consumed = false;
+                for item in funcs.pairs::<Value, Function>() {
+                    let (_, func) = item?;
+                    consumed |= game_scope(&self.lua, game, status_bar, |bs| {
+                        func.call::<_, Option<bool>>((bs, format!("{key:?}")))
+                    })?
+                    .unwrap_or(false);
+                }
+                Ok(consumed)
+            });
+
+        match result {
+            Ok(true) => HookAction::Stop,
+            Ok(false) => HookAction::Continue(()),
+            Err(e) => {
+                self.report_error("Key", &e);
+                HookAction::Continue(())
+            }
+        }
+    }
+
+    fn generate_board(&mut self, name: &str, seed: u32) -> Option<Result<String, String>> {
+        if scripts::is_disabled(self.id) {
+            return None;
+        }
+
+        let func = self
+            .lua
+            .globals()
+            .get::<_, Table>("__GENERATORS__")
+            .and_then(|x| x.raw_get::<_, Option<Function>>(name))
+            .ok()??;
+
+        Some(
+            with_budget(&self.lua, || func.call::<_, String>(seed)).map_err(|e| {
+                self.report_error("Generator", &e);
+                e.to_string()
+            }),
+        )
+    }
+
+    fn background_draw_hook(&self, data: &DrawHookData) -> HookAction<()> {
+        if scripts::is_disabled(self.id) {
+            return HookAction::Continue(());
+        }
+
+        match self.hook_callbacks::<_, Option<bool>>(
+            None,
+            "__ON_BACKGROUND_FUNCTIONS__",
+            (data.x, data.y, data.w, data.h),
+        ) {
+            Ok(results) if results.contains(&Some(true)) => HookAction::Stop,
+            Ok(_) => HookAction::Continue(()),
+            Err(e) => {
+                self.report_error("Background", &e);
+                HookAction::Continue(())
+            }
+        }
+    }
+
+    fn cell_text_draw_hook(
+        &self,
+        _drawing: &DrawingSettings,
+        game: &SudokuGame,
+        index: u8,
+        value: u8,
+        data: &DrawHookData,
+    ) -> HookAction<()> {
+        if scripts::is_disabled(self.id) {
+            return HookAction::Continue(());
+        }
+
+        match self.hook_callbacks::<_, Option<bool>>(
+            Some(game),
+            "__ON_CELL_DRAW_FUNCTIONS__",
+            (index, value, data.x, data.y, data.w, data.h),
+        ) {
+            Ok(results) if results.contains(&Some(true)) => HookAction::Stop,
+            Ok(_) => HookAction::Continue(()),
+            Err(e) => {
+                self.report_error("CellDraw", &e);
+                HookAction::Continue(())
+            }
+        }
+    }
+
+    fn cell_text_colour_hook(&self, game: &SudokuGame, index: u8) -> Option<HookAction<AppColour>> {
+        if scripts::is_disabled(self.id) {
+            return None;
+        }
+
+        let results = self
+            .hook_callbacks::<_, Option<String>>(Some(game), "__ON_CELL_COLOUR_FUNCTIONS__", index)
+            .map_err(|e| self.report_error("CellColour", &e))
+            .ok()?;
+
+        let colour = results.into_iter().flatten().last()?;
+        match AppColour::from_str(&colour) {
+            Ok(colour) => Some(HookAction::Continue(colour)),
+            Err(()) => {
+                self.report_error(
+                    "CellColour",
+                    &RuntimeError(format!("invalid cell colour '{colour}'")),
+                );
+                None
+            }
+        }
+    }
 }
 
 impl LuaScript {
     fn exec(name: &str, code: &str, status_bar: &StatusBar) -> LuaResult<LuaScript> {
         let lua = Lua::new_with(StdLib::ALL_SAFE, LuaOptions::default())?;
+        limit_lua(&lua)?;
 
         let scr = Self {
+            id: scripts::register(name),
             lua,
             name: name.to_string(),
+            watch: None,
         };
 
+        scr.lua.set_app_data(PendingSolves::default());
+
         scr.load_internal_lib()?;
         scr.load_logging_lib()?;
         scr.load_events_lib()?;
         scr.load_drawing_lib(status_bar.drawing.clone())?;
         scr.load_cursor_lib()?;
+        scr.load_keyboard_lib()?;
+        scr.load_stats_lib()?;
+        scr.load_storage_lib()?;
 
-        scr.lua.load(code).set_name(name).exec()?;
+        with_budget(&scr.lua, || scr.lua.load(code).set_name(name).exec())?;
 
         Ok(scr)
     }
 
+    fn report_error(&self, context: &str, error: &mlua::Error) {
+        scripts::report_error(self.id, context, &error.to_string());
+
+        let exceeded_budget = self
+            .lua
+            .app_data_mut::<CallBudget>()
+            .is_some_and(|mut x| std::mem::take(&mut x.exceeded));
+        if exceeded_budget {
+            scripts::disable(self.id, "a callback exceeded its time budget");
+        } else if is_memory_error(error) {
+            scripts::disable(self.id, "it exceeded its memory limit");
+        }
+    }
+
+    /// Writes changes made with the storage library, which are buffered until the end of a frame
+    fn flush_storage(&self) {
+        if let Some(store) = self.lua.app_data_ref::<Rc<RefCell<Store>>>() {
+            store.borrow_mut().flush();
+        }
+    }
+
+    /// Re-executes the watched script in place, keeping its position in the status bar. The plain
+    /// global variables of the old script are passed to the `on_reload` callbacks of the new one
+    fn reload(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar) -> LuaResult<()> {
+        let Some(watch) = &self.watch else {
+            return Ok(());
+        };
+        let code = std::fs::read_to_string(&watch.path).map_err(mlua::Error::external)?;
+        // The new script loads its store from disk, so this script's changes must be written first
+        self.flush_storage();
+
+        let mut previous = vec![];
+        for pair in self.lua.globals().pairs::<Value, Value>() {
+            let (key, value) = pair?;
+            let is_internal = matches!(&key, Value::String(x) if x.to_str()?.starts_with("__"));
+            if is_internal {
+                continue;
+            }
+
+            // Anything which can't be stored (such as functions and libraries) isn't carried over
+            if let (Ok(Some(key)), Ok(Some(value))) =
+                (to_stored_value(key, 0), to_stored_value(value, 0))
+            {
+                previous.push((key, value));
+            }
+        }
+
+        let mut scr = LuaScript::exec(&self.name, &code, status_bar)?;
+        scr.generic_game_callback(game, status_bar, "__ON_INIT_FUNCTIONS__")?;
+
+        scr.reload_callbacks(game, status_bar, &StoredValue::Table(previous))?;
+
+        scr.watch = self.watch.take();
+        *self = scr;
+        Ok(())
+    }
+
+    fn reload_callbacks(
+        &self,
+        sudoku: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+        previous: &StoredValue,
+    ) -> LuaResult<()> {
+        let previous = from_stored_value(&self.lua, previous)?;
+        let funcs = self
+            .lua
+            .globals()
+            .get::<_, Table>("__ON_RELOAD_FUNCTIONS__")?;
+
+        for item in funcs.pairs::<Value, Function>() {
+            let (_, func) = item?;
+            game_scope(&self.lua, sudoku, status_bar, |bs| {
+                func.call::<_, ()>((bs, previous.clone()))
+            })?;
+        }
+
+        Ok(())
+    }
+
     fn load_internal_lib(&self) -> LuaResult<()> {
         self.lua.globals().set(
             "__systime_ms__",
@@ -210,6 +937,58 @@ impl LuaScript {
             })?,
         )?;
 
+        self.lua.globals().set(
+            "__gametime_ms__",
+            self.lua
+                .create_function(move |_, ()| Ok(game_clock::game_time_ms()))?,
+        )?;
+
+        let loading = Rc::new(RefCell::new(Vec::<String>::new()));
+        self.lua
+            .globals()
+            .set("__LOADED_MODULES__", self.lua.create_table()?)?;
+        self.lua.globals().set(
+            "require",
+            self.lua.create_function(move |lua, module: String| {
+                let loaded = lua.globals().get::<_, Table>("__LOADED_MODULES__")?;
+                if let Some(value) = loaded.get::<_, Option<Value>>(module.as_str())? {
+                    return Ok(value);
+                }
+
+                if loading.borrow().contains(&module) {
+                    Err(RuntimeError(format!("Module '{module}' requires itself")))?;
+                }
+
+                let (path, code) = outside_budget(lua, || {
+                    let path = plugins::find_module(&module)
+                        .ok_or_else(|| RuntimeError(format!("Module '{module}' not found")))?;
+                    let code = std::fs::read_to_string(&path).map_err(mlua::Error::external)?;
+                    Ok::<_, mlua::Error>((path, code))
+                })?;
+                trace!("Loading module '{module}' from '{}'", path.display());
+
+                loading.borrow_mut().push(module.clone());
+                let value = lua
+                    .load(code)
+                    .set_name(path.to_string_lossy())
+                    .eval::<Value>();
+                loading.borrow_mut().retain(|x| *x != module);
+
+                let value = match value? {
+                    Value::Nil => Value::Boolean(true),
+                    x => x,
+                };
+                loaded.set(module, value.clone())?;
+                Ok(value)
+            })?,
+        )?;
+
+        self.lua.globals().set(
+            "__paused__",
+            self.lua
+                .create_function(move |_, ()| Ok(game_clock::is_paused()))?,
+        )?;
+
         Ok(())
     }
 
@@ -234,15 +1013,35 @@ events["on_update"] = function(callback)
     table.insert(__ON_UPDATE_FUNCTIONS__, callback)
 end
 
+__ON_RELOAD_FUNCTIONS__ = {}
+events["on_reload"] = function(callback)
+    table.insert(__ON_RELOAD_FUNCTIONS__, callback)
+end
+
+__ON_CELL_DRAW_FUNCTIONS__ = {}
+events["on_cell_draw"] = function(callback)
+    table.insert(__ON_CELL_DRAW_FUNCTIONS__, callback)
+end
+
+__ON_CELL_COLOUR_FUNCTIONS__ = {}
+events["on_cell_colour"] = function(callback)
+    table.insert(__ON_CELL_COLOUR_FUNCTIONS__, callback)
+end
+
+__ON_BACKGROUND_FUNCTIONS__ = {}
+events["on_background"] = function(callback)
+    table.insert(__ON_BACKGROUND_FUNCTIONS__, callback)
+end
+
 
 __WAIT_FUNCTIONS__ = {}
 events["wait_ms"] = function(ms, callback)
-    table.insert(__WAIT_FUNCTIONS__, { target = __systime_ms__() + ms, cb = callback, interval = -1 })
+    table.insert(__WAIT_FUNCTIONS__, { target = __gametime_ms__() + ms, cb = callback, interval = -1 })
 end
 
 __WAIT_FUNCTIONS__ = {}
 events["repeat_ms"] = function(ms, callback)
-    table.insert(__WAIT_FUNCTIONS__, { target = __systime_ms__() + 1, cb = callback, interval = ms })
+    table.insert(__WAIT_FUNCTIONS__, { target = __gametime_ms__() + 1, cb = callback, interval = ms })
 end
 
 print = info
@@ -254,14 +1053,50 @@ script["on_name"] = function(callback)
     __ON_NAME_FUNCTION = callback
 end
 
-__ON_UPDATE_FUNCTION = {}
-script["on_update"] = function(callback)
-    __ON_UPDATE_FUNCTION = callback
+__ON_HELP_FUNCTION = {}
+script["on_help"] = function(callback)
+    __ON_HELP_FUNCTION = callback
+end
+
+__ON_UPDATE_FUNCTION = {}
+script["on_update"] = function(callback)
+    __ON_UPDATE_FUNCTION = callback
+end
+
+__ON_ACTIVATE_FUNCTION = function(_game, _buffer) end
+script["on_activate"] = function(callback)
+    __ON_ACTIVATE_FUNCTION = callback
+end
+
+commands = {}
+
+__PENDING_COMMANDS__ = {}
+commands["run"] = function(cmd)
+    return __RUN_COMMAND__(cmd)
+end
+
+commands["run_async"] = function(cmd, callback)
+    __PENDING_COMMANDS__[__QUEUE_COMMAND__(cmd)] = callback
+end
+
+__PENDING_SOLVES__ = {}
+
+status_bar = {}
+status_bar["items"] = function() return __STATUS_BAR_ITEMS__() end
+status_bar["activate"] = function(name, buffer)
+    return __RUN_COMMAND__(name .. " " .. (buffer or ""))
 end
+status_bar["remove"] = function(name) __STATUS_BAR_REMOVE__(name) end
+status_bar["move"] = function(name, index) __STATUS_BAR_MOVE__(name, index) end
+status_bar["register"] = function(item) __STATUS_BAR_REGISTER__(item) end
+status_bar["buffer"] = function() return __STATUS_BAR_BUFFER__() end
+status_bar["set_buffer"] = function(buffer) __STATUS_BAR_SET_BUFFER__(buffer) end
 
-__ON_ACTIVATE_FUNCTION = function(_game, _buffer) end
-script["on_activate"] = function(callback)
-    __ON_ACTIVATE_FUNCTION = callback
+generators = {}
+
+__GENERATORS__ = {}
+generators["register"] = function(name, callback)
+    __GENERATORS__[name] = callback
 end
 
 __DISPLAY_MODE = "Normal"
@@ -283,33 +1118,32 @@ script["display_status_only"] = function() __DISPLAY_MODE = "StatusOnly" end
             self.lua.create_function(|_, ()| Ok(screen_size()))?,
         )?;
 
+        let draw_settings_2 = draw_settings.clone();
         drawing.set(
             "game_size",
-            self.lua.create_function(|_, ()| {
-                let (x, mut y) = screen_size();
-                y -= get_status_bar_height();
-
-                Ok((x, y))
-            })?,
+            self.lua
+                .create_function(move |_, ()| Ok(draw_settings_2.layout().game_size))?,
         )?;
+        let draw_settings_2 = draw_settings.clone();
         drawing.set(
             "game_origin",
-            self.lua.create_function(|_, ()| Ok((0.0f32, 0.0f32)))?,
+            self.lua
+                .create_function(move |_, ()| Ok(draw_settings_2.layout().game_origin))?,
         )?;
+        let draw_settings_2 = draw_settings.clone();
         drawing.set(
             "status_bar_size",
-            self.lua.create_function(|_, ()| {
-                let (x, y) = (screen_width(), get_status_bar_height());
+            self.lua.create_function(move |_, ()| {
+                let (x, y) = (screen_width(), get_status_bar_height(&draw_settings_2));
                 Ok((x, y))
             })?,
         )?;
 
+        let draw_settings_2 = draw_settings.clone();
         drawing.set(
             "status_bar_origin",
-            self.lua.create_function(|_, ()| {
-                let (x, y) = (0.0f32, screen_height() - get_status_bar_height());
-                Ok((x, y))
-            })?,
+            self.lua
+                .create_function(move |_, ()| Ok(draw_settings_2.status_bar_layout().origin()))?,
         )?;
         let draw_settings_2 = draw_settings.clone();
         drawing.set(
@@ -331,6 +1165,133 @@ script["display_status_only"] = function() __DISPLAY_MODE = "StatusOnly" end
                 Ok(())
             })?,
         )?;
+        drawing.set(
+            "draw_rect_lines",
+            self.lua
+                .create_function(|_, (x, y, w, h, thickness, r, g, b, a)| {
+                    draw_rectangle_lines(x, y, w, h, thickness, Color::new(r, g, b, a));
+                    Ok(())
+                })?,
+        )?;
+        drawing.set(
+            "draw_line",
+            self.lua
+                .create_function(|_, (x1, y1, x2, y2, thickness, r, g, b, a)| {
+                    draw_line(x1, y1, x2, y2, thickness, Color::new(r, g, b, a));
+                    Ok(())
+                })?,
+        )?;
+        drawing.set(
+            "draw_circle",
+            self.lua.create_function(|_, (x, y, radius, r, g, b, a)| {
+                draw_circle(x, y, radius, Color::new(r, g, b, a));
+                Ok(())
+            })?,
+        )?;
+        drawing.set(
+            "draw_poly",
+            self.lua
+                .create_function(|_, (x, y, sides, radius, rotation, r, g, b, a)| {
+                    draw_poly(x, y, sides, radius, rotation, Color::new(r, g, b, a));
+                    Ok(())
+                })?,
+        )?;
+        drawing.set(
+            "load_image",
+            self.lua.create_function(|lua, file_name: String| {
+                let path = config::get_sandboxed_file_path(&file_name).ok_or_else(|| {
+                    RuntimeError(format!(
+                        "Image '{file_name}' must be a relative path within the config directory"
+                    ))
+                })?;
+
+                if lua.app_data_ref::<ImageCache>().is_none() {
+                    lua.set_app_data(ImageCache::default());
+                }
+                let mut cache = lua.app_data_mut::<ImageCache>().unwrap();
+                if let Some(texture) = cache.0.get(&path) {
+                    return Ok(LuaImage(texture.clone()));
+                }
+
+                let texture = outside_budget(lua, || {
+                    let file = std::fs::read(&path)
+                        .map_err(|_| RuntimeError(format!("Could not find image '{file_name}'")))?;
+                    let image = Image::from_file_with_format(&file, None)
+                        .map_err(|e| RuntimeError(format!("Image is not valid: {e}")))?;
+                    Ok::<_, mlua::Error>(Texture2D::from_image(&image))
+                })?;
+                cache.0.insert(path, texture.clone());
+                Ok(LuaImage(texture))
+            })?,
+        )?;
+        drawing.set(
+            "draw_image",
+            self.lua.create_function(
+                |_,
+                 (image, x, y, w, h, a): (
+                    LuaUserDataRef<LuaImage>,
+                    f32,
+                    f32,
+                    Option<f32>,
+                    Option<f32>,
+                    Option<f32>,
+                )| {
+                    let texture = &image.0;
+                    draw_texture_ex(
+                        texture,
+                        x,
+                        y,
+                        Color::new(1.0, 1.0, 1.0, a.unwrap_or(1.0)),
+                        DrawTextureParams {
+                            dest_size: Some(Vec2::new(
+                                w.unwrap_or(texture.width()),
+                                h.unwrap_or(texture.height()),
+                            )),
+                            ..Default::default()
+                        },
+                    );
+                    Ok(())
+                },
+            )?,
+        )?;
+        let draw_settings_2 = draw_settings.clone();
+        drawing.set(
+            "measure_text",
+            self.lua
+                .create_function(move |_, (text, size): (String, f32)| {
+                    Ok(draw_settings_2.measure_text(&text, size))
+                })?,
+        )?;
+        let draw_settings_2 = draw_settings.clone();
+        drawing.set(
+            "cell_rect",
+            self.lua.create_function(move |_, idx: u32| {
+                if idx >= 81 {
+                    return Err(RuntimeError("Cell index out of range".to_string()));
+                }
+                Ok(draw_settings_2.layout().cell_rect(idx))
+            })?,
+        )?;
+        let draw_settings_2 = draw_settings.clone();
+        drawing.set(
+            "box_rect",
+            self.lua.create_function(move |_, idx: u32| {
+                unit_index(idx)?;
+                Ok(draw_settings_2.layout().box_rect(idx))
+            })?,
+        )?;
+        let draw_settings_2 = draw_settings.clone();
+        drawing.set(
+            "board_rect",
+            self.lua
+                .create_function(move |_, ()| Ok(draw_settings_2.layout().board_rect()))?,
+        )?;
+        let draw_settings_2 = draw_settings.clone();
+        drawing.set(
+            "hovered_cell",
+            self.lua
+                .create_function(move |_, ()| Ok(draw_settings_2.layout().hovered_cell))?,
+        )?;
         drawing.set(
             "draw_text",
             self.lua
@@ -361,19 +1322,150 @@ script["display_status_only"] = function() __DISPLAY_MODE = "StatusOnly" end
         )?;
         cursor.set(
             "down",
-            self.lua
-                .create_function(|_, ()| Ok(is_mouse_button_down(MouseButton::Left)))?,
+            self.lua.create_function(|_, button: Option<String>| {
+                Ok(is_mouse_button_down(mouse_button(button)?))
+            })?,
         )?;
         cursor.set(
             "pressed",
-            self.lua
-                .create_function(|_, ()| Ok(is_mouse_button_pressed(MouseButton::Left)))?,
+            self.lua.create_function(|_, button: Option<String>| {
+                Ok(is_mouse_button_pressed(mouse_button(button)?))
+            })?,
+        )?;
+        cursor.set(
+            "released",
+            self.lua.create_function(|_, button: Option<String>| {
+                Ok(is_mouse_button_released(mouse_button(button)?))
+            })?,
+        )?;
+        cursor.set(
+            "scroll",
+            self.lua.create_function(|_, ()| Ok(mouse_wheel()))?,
         )?;
 
         self.lua.globals().set("cursor", cursor)?;
         Ok(())
     }
 
+    fn load_keyboard_lib(&self) -> LuaResult<()> {
+        let keyboard = self.lua.create_table()?;
+        keyboard.set(
+            "is_down",
+            self.lua.create_function(|_, key: String| {
+                Ok(get_keys_down().iter().any(|x| key_matches(*x, &key)))
+            })?,
+        )?;
+        keyboard.set(
+            "pressed",
+            self.lua.create_function(|_, key: String| {
+                Ok(get_keys_pressed().iter().any(|x| key_matches(*x, &key)))
+            })?,
+        )?;
+        keyboard.set(
+            "last_char",
+            self.lua.create_function(|_, ()| {
+                Ok(InputAction::get_last_raw_char().map(|x| x.to_string()))
+            })?,
+        )?;
+
+        self.lua.globals().set("keyboard", keyboard)?;
+
+        self.lua
+            .load(
+                r#"
+__ON_KEY_FUNCTIONS__ = {}
+keyboard["on_key"] = function(callback)
+    table.insert(__ON_KEY_FUNCTIONS__, callback)
+end
+"#,
+            )
+            .exec()
+    }
+
+    fn load_storage_lib(&self) -> LuaResult<()> {
+        let store = Rc::new(RefCell::new(Store::load(&self.name)));
+        self.lua.set_app_data(store.clone());
+        let storage = self.lua.create_table()?;
+
+        let get_store = store.clone();
+        storage.set(
+            "get",
+            self.lua.create_function(move |lua, key: String| {
+                match get_store.borrow().get(&key) {
+                    Some(value) => from_stored_value(lua, value),
+                    None => Ok(Value::Nil),
+                }
+            })?,
+        )?;
+
+        let set_store = store.clone();
+        storage.set(
+            "set",
+            self.lua
+                .create_function(move |_, (key, value): (String, Value)| {
+                    match to_stored_value(value, 0)? {
+                        Some(value) => set_store.borrow_mut().set(&key, value),
+                        None => set_store.borrow_mut().delete(&key),
+                    }
+                    Ok(())
+                })?,
+        )?;
+
+        let delete_store = store.clone();
+        storage.set(
+            "delete",
+            self.lua.create_function(move |_, key: String| {
+                delete_store.borrow_mut().delete(&key);
+                Ok(())
+            })?,
+        )?;
+
+        storage.set(
+            "keys",
+            self.lua.create_function(move |lua, ()| {
+                lua.create_sequence_from(store.borrow().keys().cloned())
+            })?,
+        )?;
+
+        self.lua.globals().set("storage", storage)?;
+        Ok(())
+    }
+
+    fn load_stats_lib(&self) -> LuaResult<()> {
+        let stats = self.lua.create_table()?;
+        stats.set(
+            "records",
+            self.lua.create_function(|lua, ()| {
+                let table = lua.create_table()?;
+                for record in stats::with_records(<[_]>::to_vec) {
+                    let entry = lua.create_table()?;
+                    entry.set("clues", record.clues)?;
+                    entry.set("time_ms", record.time_ms)?;
+                    entry.set("mistakes", record.mistakes)?;
+                    entry.set("hints", record.hints)?;
+                    entry.set("pause_ms", record.pause_ms)?;
+                    table.push(entry)?;
+                }
+                Ok(table)
+            })?,
+        )?;
+        stats.set(
+            "best",
+            self.lua.create_function(|_, clues: u8| {
+                Ok(stats::with_records(|x| stats::summarise(x, clues)).best_ms)
+            })?,
+        )?;
+        stats.set(
+            "average",
+            self.lua.create_function(|_, clues: u8| {
+                Ok(stats::with_records(|x| stats::summarise(x, clues)).average_ms)
+            })?,
+        )?;
+
+        self.lua.globals().set("stats", stats)?;
+        Ok(())
+    }
+
     fn load_logging_lib(&self) -> LuaResult<()> {
         let name_2 = self.name.to_string();
         self.lua.globals().set(
@@ -433,16 +1525,18 @@ script["display_status_only"] = function() __DISPLAY_MODE = "StatusOnly" end
         Ok(())
     }
 
-    fn generic_game_callback(&self, sudoku: &mut SudokuGame, name: &str) -> LuaResult<()> {
+    fn generic_game_callback(
+        &self,
+        sudoku: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+        name: &str,
+    ) -> LuaResult<()> {
         let funcs = self.lua.globals().get::<_, Table>(name)?;
 
         for item in funcs.pairs::<Value, Function>() {
             let (_, func) = item?;
 
-            self.lua.scope(|scope| {
-                let bs = scope.create_userdata_ref_mut(sudoku)?;
-                func.call(bs)
-            })?;
+            game_scope(&self.lua, sudoku, status_bar, |bs| func.call::<_, ()>(bs))?;
         }
 
         Ok(())
@@ -450,21 +1544,114 @@ script["display_status_only"] = function() __DISPLAY_MODE = "StatusOnly" end
 
     fn generic_single_callback<'lua, T: FromLuaMulti<'lua>>(
         &'lua self,
-        sudoku: Option<&mut SudokuGame>,
+        state: Option<(&mut SudokuGame, &mut StatusBar)>,
         name: &str,
     ) -> LuaResult<T> {
         let func = self.lua.globals().get::<_, Function>(name)?;
 
-        self.lua.scope(|scope| match sudoku {
-            Some(sudoku) => {
-                let bs = scope.create_userdata_ref_mut(sudoku)?;
-                func.call::<_, T>(bs)
+        match state {
+            Some((sudoku, status_bar)) => {
+                game_scope(&self.lua, sudoku, status_bar, |bs| func.call::<_, T>(bs))
             }
-            None => func.call::<_, T>(()),
+            None => with_budget(&self.lua, || func.call::<_, T>(())),
+        }
+    }
+
+    /// Calls each callback in the table `name` with `args`, preceded by a read only `Game` if given,
+    /// for use within the drawing hooks, which don't have access to the status bar
+    fn hook_callbacks<'lua, A, R>(
+        &'lua self,
+        game: Option<&SudokuGame>,
+        name: &str,
+        args: A,
+    ) -> LuaResult<Vec<R>>
+    where
+        A: IntoLuaMulti<'lua>,
+        R: FromLua<'lua>,
+    {
+        let funcs = self.lua.globals().get::<_, Table>(name)?;
+        if funcs.raw_len() == 0 {
+            return Ok(vec![]);
+        }
+
+        let args = args.into_lua_multi(&self.lua)?;
+        with_budget(&self.lua, || {
+            self.lua.scope(|scope| {
+                let mut args = args;
+                if let Some(game) = game {
+                    args.push_front(Value::UserData(scope.create_userdata_ref(game)?));
+                }
+
+                let mut results = vec![];
+                for item in funcs.pairs::<Value, Function>() {
+                    let (_, func) = item?;
+                    results.push(func.call::<_, R>(args.clone())?);
+                }
+                Ok(results)
+            })
         })
     }
 
-    fn update_wait_funcs(&self, sudoku: &mut SudokuGame) -> LuaResult<()> {
+    fn update_pending_commands(
+        &self,
+        sudoku: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+    ) -> LuaResult<()> {
+        let pending = self.lua.globals().get::<_, Table>("__PENDING_COMMANDS__")?;
+
+        let mut finished = vec![];
+        for item in pending.clone().pairs::<u64, Function>() {
+            let (id, func) = item?;
+            if let Some(result) = status_bar.take_command_result(id) {
+                finished.push((id, func, result));
+            }
+        }
+
+        for (id, func, result) in finished {
+            pending.raw_remove(id)?;
+            game_scope(&self.lua, sudoku, status_bar, |bs| {
+                func.call::<_, ()>((bs, result.status, result.buffer))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn update_pending_solves(
+        &self,
+        sudoku: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+    ) -> LuaResult<()> {
+        let mut finished = vec![];
+        if let Some(mut solves) = self.lua.app_data_mut::<PendingSolves>() {
+            solves.tasks.retain(|id, task| {
+                task.update_status();
+                match task.get() {
+                    TaskStatus::Waiting(_) => return true,
+                    TaskStatus::Done(game) => finished.push((*id, Some(game.as_ref().clone()))),
+                    TaskStatus::Failed => finished.push((*id, None)),
+                }
+                false
+            });
+        }
+
+        let pending = self.lua.globals().get::<_, Table>("__PENDING_SOLVES__")?;
+        for (id, solved) in finished {
+            let func = pending.raw_get::<_, Function>(id)?;
+            pending.raw_remove(id)?;
+            game_scope(&self.lua, sudoku, status_bar, |bs| {
+                func.call::<_, ()>((bs, solved))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn update_wait_funcs(
+        &self,
+        sudoku: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+    ) -> LuaResult<()> {
         let funcs = self.lua.globals().get::<_, Table>("__WAIT_FUNCTIONS__")?;
         let mut remove_keys = vec![];
 
@@ -475,22 +1662,18 @@ script["display_status_only"] = function() __DISPLAY_MODE = "StatusOnly" end
             let interval = table.get::<_, i32>("interval")?;
             let mut repeating = interval != -1;
 
-            let time = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|x| x.as_millis())
-                .unwrap_or(0);
+            let time = game_clock::game_time_ms();
 
             if time < wanted_ms {
                 continue;
             }
 
-            self.lua.scope(|scope| {
-                let bs = scope.create_userdata_ref_mut(sudoku)?;
+            game_scope(&self.lua, sudoku, status_bar, |bs| {
                 if repeating {
                     repeating = func.call::<_, bool>(bs)?;
                     Ok(())
                 } else {
-                    func.call(bs)
+                    func.call::<_, ()>(bs)
                 }
             })?;
 
@@ -510,7 +1693,178 @@ script["display_status_only"] = function() __DISPLAY_MODE = "StatusOnly" end
     }
 }
 
-impl LuaRun {
+impl ReplSession {
+    fn new(name: &str) -> LuaResult<Self> {
+        let lua = Lua::new_with(StdLib::ALL_SAFE, LuaOptions::default())?;
+        limit_lua(&lua)?;
+        Ok(Self {
+            name: name.to_string(),
+            lua,
+            pending: String::new(),
+            history: vec![],
+            history_offset: 0,
+        })
+    }
+
+    /// The buffer prefix which evaluates code in this session
+    fn prompt(&self) -> String {
+        if self.name == DEFAULT_REPL_SESSION {
+            "=".to_string()
+        } else {
+            format!("=:{} ", self.name)
+        }
+    }
+
+    /// Evaluates a line with `Game` bound to `game`, returning the results pretty printed. If the
+    /// line leaves a chunk incomplete, it is kept until the rest of the chunk is entered
+    fn eval(&mut self, line: &str, game: &mut SudokuGame) -> LuaResult<String> {
+        self.history_offset = 0;
+        if !line.trim().is_empty() {
+            self.history.retain(|x| x != line);
+            self.history.push(line.to_string());
+        }
+
+        if !self.pending.is_empty() && line.trim().is_empty() {
+            self.pending.clear();
+            return Ok("Repl: discarded incomplete input".to_string());
+        }
+
+        let code = if self.pending.is_empty() {
+            line.to_string()
+        } else {
+            format!("{}\n{line}", self.pending)
+        };
+
+        let lua = &self.lua;
+        let result = with_budget(lua, || {
+            lua.scope(|scope| {
+                lua.globals()
+                    .set("Game", scope.create_userdata_ref_mut(game)?)?;
+                let values = lua.load(&code).set_name("Repl").eval::<MultiValue>()?;
+                let printed = values
+                    .iter()
+                    .map(|x| pretty_print(lua, x, 0, &mut vec![]))
+                    .collect::<LuaResult<Vec<_>>>()?;
+
+                // Userdata such as `Game` is destroyed when the scope ends, so only its
+                // description is kept
+                match values.iter().next() {
+                    Some(Value::UserData(_)) => lua.globals().set("_", printed[0].clone())?,
+                    Some(first) => lua.globals().set("_", first.clone())?,
+                    None => {}
+                }
+                Ok(printed.join(", "))
+            })
+        });
+
+        match result {
+            Err(mlua::Error::SyntaxError {
+                incomplete_input: true,
+                ..
+            }) => {
+                debug!("Repl input is incomplete, waiting for the next line");
+                self.pending = code;
+                Ok(self.prompt())
+            }
+            result => {
+                self.pending.clear();
+                result
+            }
+        }
+    }
+
+    /// Moves through the history of this session, returning the buffer to show
+    fn scroll_history(&mut self, up: bool) -> String {
+        if up {
+            self.history_offset = usize::min(self.history_offset + 1, self.history.len());
+        } else {
+            self.history_offset = self.history_offset.saturating_sub(1);
+        }
+
+        match self.history_offset {
+            0 => self.prompt(),
+            offset => format!(
+                "{}{}",
+                self.prompt(),
+                self.history[self.history.len() - offset]
+            ),
+        }
+    }
+}
+
+/// Splits the session name from `Repl` input, which is selected with a `:session` prefix
+fn split_repl_session(input: &str) -> (&str, &str) {
+    match input.strip_prefix(':') {
+        Some(rest) => {
+            let (name, code) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            (name, code.trim_start())
+        }
+        None => (DEFAULT_REPL_SESSION, input),
+    }
+}
+
+/// Formats a value for the `Repl`, expanding tables (up to a depth) rather than printing their
+/// address. Strings are only quoted within tables
+fn pretty_print(
+    lua: &Lua,
+    value: &Value,
+    depth: usize,
+    seen: &mut Vec<*const std::ffi::c_void>,
+) -> LuaResult<String> {
+    match value {
+        Value::String(x) if depth == 0 => Ok(x.to_string_lossy().to_string()),
+        Value::String(x) => Ok(format!("{:?}", x.to_string_lossy())),
+        Value::Table(table) => {
+            if seen.contains(&table.to_pointer()) {
+                return Ok("<cycle>".to_string());
+            }
+            if depth >= MAX_PRETTY_PRINT_DEPTH {
+                return Ok("{...}".to_string());
+            }
+
+            seen.push(table.to_pointer());
+            let entries = table
+                .clone()
+                .pairs::<Value, Value>()
+                .collect::<LuaResult<Vec<_>>>()?;
+
+            let mut printed = vec![];
+            if entries.len() == table.raw_len() {
+                for value in table.clone().sequence_values::<Value>() {
+                    printed.push(pretty_print(lua, &value?, depth + 1, seen)?);
+                }
+            } else {
+                for (key, value) in &entries {
+                    let value = pretty_print(lua, value, depth + 1, seen)?;
+                    printed.push(match key {
+                        Value::String(key)
+                            if key.to_str().is_ok_and(|x| {
+                                x.chars().all(|c| c.is_alphanumeric() || c == '_')
+                            }) =>
+                        {
+                            format!("{} = {value}", key.to_string_lossy())
+                        }
+                        key => format!("[{}] = {value}", pretty_print(lua, key, depth + 1, seen)?),
+                    });
+                }
+                printed.sort();
+            }
+            seen.pop();
+
+            if printed.len() > MAX_PRETTY_PRINT_ENTRIES {
+                printed.truncate(MAX_PRETTY_PRINT_ENTRIES);
+                printed.push("...".to_string());
+            }
+            Ok(format!("{{{}}}", printed.join(", ")))
+        }
+        value => lua
+            .globals()
+            .get::<_, Function>("tostring")?
+            .call::<_, String>(value.clone()),
+    }
+}
+
+impl LuaRun<'_> {
     fn code(&self) -> String {
         match self {
             LuaRun::File { code, .. } | LuaRun::Repl { code, .. } => code.to_string(),
@@ -520,11 +1874,15 @@ impl LuaRun {
     fn name(&self) -> String {
         match self {
             LuaRun::File { name, .. } => name.to_string(),
-            LuaRun::Repl { .. } => "Repl".to_string(),
+            LuaRun::Repl { session, .. } => format!("Repl:{}", session.name),
         }
     }
 
-    fn run(&self, game: &mut SudokuGame, status_bar: &mut StatusBar) -> LuaResult<Option<String>> {
+    fn run(
+        &mut self,
+        game: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+    ) -> LuaResult<Option<String>> {
         let span = span!(Level::INFO, "RunLua");
         let _enter = span.enter();
 
@@ -533,15 +1891,21 @@ impl LuaRun {
 
         match self {
             LuaRun::File {
-                allow_duplicate, ..
+                allow_duplicate,
+                watch,
+                ..
             } => {
                 info!("Executing Lua script: {name}...");
-                let scr = LuaScript::exec(&name, &code, status_bar)?;
-                scr.generic_game_callback(game, "__ON_INIT_FUNCTIONS__")?;
+                let mut scr = LuaScript::exec(&name, &code, status_bar)?;
+                scr.generic_game_callback(game, status_bar, "__ON_INIT_FUNCTIONS__")?;
+                if *watch {
+                    debug!("Watching '{name}' for changes");
+                    scr.watch = Some(ScriptWatch::new(config::get_file_path(&name)));
+                }
 
                 let mut remove = None;
 
-                if !allow_duplicate {
+                if !*allow_duplicate {
                     for (i, item) in status_bar.items.iter_mut().enumerate() {
                         if let ItemStatus::Ok(ItemOkData::LuaScript(sc_name)) = item.status() {
                             if name == sc_name {
@@ -561,25 +1925,41 @@ impl LuaRun {
 
                 Ok(None)
             }
-            LuaRun::Repl { .. } => {
-                let lua = Lua::new_with(StdLib::ALL_SAFE, LuaOptions::default())?;
-                lua.scope(|ctx| {
-                    let ud_game = ctx.create_userdata_ref_mut(game)?;
-                    lua.globals().set("Game", ud_game)?;
-                    let chunk = lua.load(code).set_name(name);
-                    chunk.eval::<String>()
-                })
-                .map(Some)
-            }
+            LuaRun::Repl { session, .. } => session.eval(&code, game).map(Some),
         }
     }
 }
 
+/// Loads a script file from the config directory, as if it were loaded with `Eval @file_name`
+pub fn load_script_file(
+    file_name: &str,
+    game: &mut SudokuGame,
+    status_bar: &mut StatusBar,
+) -> Result<(), String> {
+    let Ok(code) = std::fs::read_to_string(config::get_file_path(file_name)) else {
+        return Err(format!("FileNotFound: {file_name}"));
+    };
+
+    LuaRun::File {
+        code,
+        allow_duplicate: false,
+        watch: false,
+        name: file_name.to_string(),
+    }
+    .run(game, status_bar)
+    .map(|_| ())
+    .map_err(|e| format!("LuaError: {e}"))
+}
+
 impl Item for Eval {
     fn name(&self) -> String {
         "Eval".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("Eval ([code])|(@[!~]?[script_file])".to_string())
+    }
+
     fn activated(
         &mut self,
         game: &mut crate::sudoku_game::SudokuGame,
@@ -588,12 +1968,19 @@ impl Item for Eval {
         let span = span!(Level::INFO, "EvalActivated");
         let _enter = span.enter();
 
-        let code = match (status_bar.buffer.get(0..1), status_bar.buffer.get(1..)) {
+        let mut code = match (status_bar.buffer.get(0..1), status_bar.buffer.get(1..)) {
             (Some("@"), Some(file_name)) => {
                 let mut file_name = file_name.to_string();
                 let mut allow_duplicate = false;
-                if file_name.starts_with('!') {
-                    allow_duplicate = true;
+                let mut watch = false;
+                loop {
+                    if file_name.starts_with('!') {
+                        allow_duplicate = true;
+                    } else if file_name.starts_with('~') {
+                        watch = true;
+                    } else {
+                        break;
+                    }
                     file_name.remove(0);
                 }
 
@@ -605,12 +1992,27 @@ impl Item for Eval {
                 LuaRun::File {
                     code: file,
                     allow_duplicate,
+                    watch,
                     name: file_name.to_string(),
                 }
             }
-            _ => LuaRun::Repl {
-                code: status_bar.buffer.to_string(),
-            },
+            _ => {
+                let (session, code) = split_repl_session(&status_bar.buffer);
+                let code = code.to_string();
+                let session = match self.sessions.entry(session.to_string()) {
+                    Entry::Occupied(x) => x.into_mut(),
+                    Entry::Vacant(x) => match ReplSession::new(session) {
+                        Ok(session) => x.insert(session),
+                        Err(e) => {
+                            status_bar.buffer = format!("LuaError: {e}");
+                            error!("Lua Error: {e}");
+                            return;
+                        }
+                    },
+                };
+
+                LuaRun::Repl { code, session }
+            }
         };
 
         match code.run(game, status_bar) {
@@ -629,7 +2031,74 @@ impl Item for Eval {
         super::DisplayMode::None
     }
 
+    fn completions(&self, _status_bar: &StatusBar, args: &[&str]) -> Vec<String> {
+        if !args.is_empty() {
+            return Vec::new();
+        }
+
+        completion::script_files()
+            .into_iter()
+            .map(|x| format!("@{x}"))
+            .collect()
+    }
+
     fn shorthands(&self) -> Option<List> {
         shorthand!((r"^=(.*)", "$1"))
     }
+
+    /// Whilst the buffer holds `Repl` input, the up and down keys move through the history of its
+    /// session, rather than the command history
+    fn key_hook(
+        &mut self,
+        game: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+        _key: KeyCode,
+    ) -> HookAction<()> {
+        let up = match InputAction::get_last_input(InputActionContext::Buffer, &game.input) {
+            Some(InputAction::UpBuffer) => true,
+            Some(InputAction::DownBuffer) => false,
+            _ => return HookAction::Continue(()),
+        };
+
+        let Some(input) = status_bar.buffer.strip_prefix('=') else {
+            return HookAction::Continue(());
+        };
+        if input.starts_with('@') {
+            return HookAction::Continue(());
+        }
+
+        let (session, _) = split_repl_session(input);
+        let Some(session) = self.sessions.get_mut(session) else {
+            return HookAction::Continue(());
+        };
+
+        status_bar.buffer = session.scroll_history(up);
+        HookAction::Stop
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slow_native_calls_keep_within_budget() {
+        let lua = Lua::new();
+        limit_lua(&lua).unwrap();
+        let slow = lua
+            .create_function(|lua, ()| {
+                outside_budget(lua, || std::thread::sleep(CALLBACK_TIME_BUDGET * 2));
+                Ok(())
+            })
+            .unwrap();
+        lua.globals().set("slow", slow).unwrap();
+
+        let result = with_budget(&lua, || lua.load("slow(); for i = 1, 1000 do end").exec());
+        assert!(result.is_ok());
+        assert!(!lua.app_data_ref::<CallBudget>().unwrap().exceeded);
+
+        let result = with_budget(&lua, || lua.load("while true do end").exec());
+        assert!(result.is_err());
+        assert!(lua.app_data_ref::<CallBudget>().unwrap().exceeded);
+    }
 }
@@ -13,6 +13,10 @@ impl Item for Find {
         "Find".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some(r"Find [\.\*]?[number]".to_string())
+    }
+
     fn activated(
         &mut self,
         game: &mut crate::sudoku_game::SudokuGame,
@@ -24,9 +28,14 @@ impl Item for Find {
         let mut buffer = status_bar.buffer.clone();
         let size = game.cells.shape()[1];
         let mut cursor_pos = game
-            .selected_cell
+            .selected_cell()
             .map_or(0, |x| SudokuGame::xy_pos_to_idx(x.0, x.1, size as u32));
-        let pos_auto_set = game.selected_cell.is_none();
+        let pos_auto_set = game.selected_cell().is_none();
+
+        if let Some(target) = buffer.strip_prefix('*').and_then(|x| x.parse::<u8>().ok()) {
+            game.select_all_with_number(target);
+            return;
+        }
 
         let mut direction = FindDirection::Ahead;
         if buffer.len() == 2 && buffer.starts_with('.') {
@@ -58,7 +67,7 @@ impl Item for Find {
                 status_bar.buffer = String::from("Could not find instance of character");
                 return;
             };
-            game.selected_cell = Some((x, y));
+            game.select((x, y));
         } else {
             status_bar.buffer = String::from("Expected to start with single digit character");
         }
@@ -69,7 +78,7 @@ impl Item for Find {
     }
 
     fn shorthands(&self) -> Option<List> {
-        shorthand![(r"^[\.]?\d$", "$0")]
+        shorthand![(r"^[\.\*]?\d$", "$0")]
     }
 }
 
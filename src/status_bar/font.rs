@@ -10,6 +10,10 @@ impl Item for Font {
         "Font".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("Font [font_file] [font_size]".to_string())
+    }
+
     fn activated(
         &mut self,
         _game: &mut crate::sudoku_game::SudokuGame,
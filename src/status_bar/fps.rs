@@ -21,6 +21,10 @@ impl Item for Fps {
         "Fps".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("Fps [target]?".to_string())
+    }
+
     fn activated(
         &mut self,
         _game: &mut crate::sudoku_game::SudokuGame,
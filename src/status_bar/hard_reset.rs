@@ -10,6 +10,10 @@ impl Item for HardReset {
         "HardReset".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("HardReset".to_string())
+    }
+
     fn activated(
         &mut self,
         game: &mut crate::sudoku_game::SudokuGame,
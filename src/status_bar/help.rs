@@ -0,0 +1,78 @@
+use tracing::{span, Level};
+
+use crate::sudoku_game::SudokuGame;
+
+use super::command::split_arguments;
+use super::{Item, StatusBar};
+
+#[derive(Default)]
+pub struct Help;
+
+impl Help {
+    fn loaded_items(status_bar: &StatusBar) -> Vec<String> {
+        status_bar
+            .items()
+            .map(|x| x.name())
+            .filter(|x| x != "Dummy")
+            .collect()
+    }
+
+    /// The usage of the item with `name`, given by the item itself if it's on the status bar,
+    /// otherwise by the registry, followed by the description in the registry
+    fn usage(status_bar: &StatusBar, name: &str) -> String {
+        let item = status_bar
+            .items()
+            .find(|x| x.name().eq_ignore_ascii_case(name))
+            .map(|x| (x.name(), x.help()));
+        let factory = status_bar.registry().get(name);
+        let description = factory
+            .filter(|x| !x.description.is_empty())
+            .map(|x| format!(" - {}", x.description))
+            .unwrap_or_default();
+
+        match (item, factory) {
+            (Some((name, Some(usage))), _) => format!("{name}: {usage}{description}"),
+            (_, Some(factory)) => format!("{}: {}{description}", factory.name, factory.usage),
+            (Some((name, None)), None) => format!("Help: '{name}' has no usage"),
+            (None, None) => format!("Help: could not find item '{name}'"),
+        }
+    }
+}
+
+impl Item for Help {
+    fn name(&self) -> String {
+        "Help".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("Help [item]?".to_string())
+    }
+
+    fn activated(&mut self, _game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "HelpActivated");
+        let _enter = span.enter();
+
+        status_bar.buffer = match split_arguments(&status_bar.buffer).first() {
+            Some(name) => Self::usage(status_bar, name),
+            None => format!(
+                "Usage: Help [item], items: {}",
+                Self::loaded_items(status_bar).join(", ")
+            ),
+        };
+    }
+
+    fn completions(&self, status_bar: &StatusBar, args: &[&str]) -> Vec<String> {
+        if !args.is_empty() {
+            return Vec::new();
+        }
+
+        Self::loaded_items(status_bar)
+            .into_iter()
+            .chain(status_bar.registry().iter().map(|x| x.name.clone()))
+            .collect()
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::None
+    }
+}
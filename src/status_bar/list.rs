@@ -0,0 +1,51 @@
+use tracing::{span, Level};
+
+use crate::sudoku_game::SudokuGame;
+
+use super::{Item, StatusBar};
+
+#[derive(Default)]
+pub struct BuiltinList;
+
+impl Item for BuiltinList {
+    fn name(&self) -> String {
+        "BuiltinList".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("BuiltinList".to_string())
+    }
+
+    fn activated(&mut self, _game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "BuiltinListActivate");
+        let _enter = span.enter();
+
+        let added = status_bar
+            .item_summaries()
+            .into_iter()
+            .map(|x| x.name)
+            .collect::<Vec<_>>();
+
+        let available = status_bar
+            .registry()
+            .iter()
+            .filter(|x| !x.singleton || !added.iter().any(|y| y.eq_ignore_ascii_case(&x.name)))
+            .map(|x| x.name.clone())
+            .collect::<Vec<_>>();
+
+        status_bar.buffer = format!(
+            "Added: {} | Available: {}",
+            added
+                .iter()
+                .enumerate()
+                .map(|(i, x)| format!("{}. {x}", i + 1))
+                .collect::<Vec<_>>()
+                .join(", "),
+            available.join(", ")
+        );
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::None
+    }
+}
@@ -1,47 +1,77 @@
 use std::cmp::Ordering;
-use std::{collections::VecDeque, fmt::Display, time::Instant};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    time::Instant,
+};
 
-use macroquad::window::screen_height;
+use macroquad::input::{get_last_key_pressed, mouse_position, mouse_wheel};
+use macroquad::miniquad::KeyCode;
+use macroquad::window::screen_width;
 use macroquad::{
     color::Color,
-    miniquad::window::screen_size,
     shapes::{draw_line, draw_rectangle},
 };
 use tracing::{debug, error, span, trace, warn, Level};
 
 use crate::status_bar::shorthands::list::List;
+use crate::status_bar::shorthands::Shorthand;
 use crate::{
     draw_helper::{
-        draw_text_in_bounds, get_normal_line_width, get_status_bar_height, AppColour,
-        DrawingSettings,
+        draw_text_in_bounds, get_normal_line_width, get_status_bar_row_height, pack_rows,
+        AppColour, DrawingSettings,
     },
     input_helper::{InputAction, InputActionChar, InputActionContext},
     sudoku_game::{ResetSignal, SudokuGame},
 };
 
-use self::{add::Add, dummy::Dummy};
+use self::command::Condition;
+use self::completion::Completion;
+use self::registry::{ItemSource, Registry};
+use self::{
+    add::Add, dummy::Dummy, help::Help, list::BuiltinList, palette::Palette, remove::BuiltinRemove,
+    reorder::BuiltinMove, set::Set,
+};
 
 mod add;
+mod alias;
 mod background_image;
+mod bar_layout;
 pub mod board_gen;
 pub mod colour_overwrite;
+mod command;
+mod completion;
 pub mod cpu_solve;
 mod dummy;
 mod find;
 mod font;
 pub mod fps;
 mod hard_reset;
+mod help;
+mod list;
 pub mod on_board_init;
 mod padding;
+mod palette;
+mod pause;
 pub mod pencil_marks;
+mod plugins;
+mod registry;
+mod remove;
+mod reorder;
+mod replay;
+mod scripts;
+mod set;
 #[macro_use]
 pub mod shorthands;
 mod eval;
+pub mod stats;
 
 #[allow(dead_code)]
 pub enum ItemOkData<'a> {
     Game(&'a SudokuGame),
     LuaScript(String),
+    /// A replay is being played back, and sets the board's cells
+    ReplayPlayback,
     None,
 }
 
@@ -66,6 +96,7 @@ impl<'a> Display for ItemStatus<'a> {
 }
 
 #[allow(dead_code)]
+#[derive(Clone, Copy)]
 pub enum DisplayMode {
     Normal,
     NameOnly,
@@ -73,6 +104,21 @@ pub enum DisplayMode {
     None,
 }
 
+impl Display for DisplayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DisplayMode::Normal => "Normal",
+                DisplayMode::NameOnly => "NameOnly",
+                DisplayMode::StatusOnly => "StatusOnly",
+                DisplayMode::None => "None",
+            }
+        )
+    }
+}
+
 pub struct DrawHookData {
     pub x: f32,
     pub y: f32,
@@ -90,6 +136,25 @@ pub trait Item {
     fn name(&self) -> String;
     fn activated(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar);
 
+    /// Usage of the item's command, shown by `Help [item]`. Built-in items are registered with it
+    /// as their usage
+    fn help(&self) -> Option<String> {
+        None
+    }
+
+    /// Candidates for the argument following `args`, which are offered when Tab is pressed in the
+    /// buffer
+    #[allow(unused_variables)]
+    fn completions(&self, status_bar: &StatusBar, args: &[&str]) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether the item's arguments are passed on without substituting variables, for items which
+    /// keep commands to run later, so that variables are substituted when those commands run
+    fn raw_arguments(&self) -> bool {
+        false
+    }
+
     #[allow(unused_variables)]
     fn update(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar) -> (String, Color) {
         (
@@ -101,7 +166,7 @@ pub trait Item {
     #[allow(unused_variables)]
     fn board_init(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar) {}
 
-    fn status(&mut self) -> ItemStatus {
+    fn status(&mut self) -> ItemStatus<'_> {
         ItemStatus::Ok(ItemOkData::None)
     }
 
@@ -134,38 +199,312 @@ pub trait Item {
     fn shorthands(&self) -> Option<List> {
         None
     }
+
+    /// Called with the key pressed this frame, before the board handles any input. Returning
+    /// `HookAction::Stop` consumes the key, so no other item or built-in handler sees it
+    #[allow(unused_variables)]
+    fn key_hook(
+        &mut self,
+        game: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+        key: KeyCode,
+    ) -> HookAction<()> {
+        HookAction::Continue(())
+    }
+
+    /// Called after the status bar is drawn, for drawing over the whole window
+    #[allow(unused_variables)]
+    fn overlay_draw_hook(&self, drawing: &DrawingSettings) {}
+
+    /// Generates a board string with the board generator `name` if this item provides it, as used
+    /// by `BoardGen gen=<name>`
+    #[allow(unused_variables)]
+    fn generate_board(&mut self, name: &str, seed: u32) -> Option<Result<String, String>> {
+        None
+    }
+}
+
+pub struct CommandResult {
+    pub status: String,
+    pub buffer: String,
+}
+
+#[derive(Clone)]
+pub struct ItemSummary {
+    pub name: String,
+    pub display_mode: DisplayMode,
+    pub status: String,
+}
+
+impl ItemSummary {
+    fn of(item: &mut dyn Item) -> Self {
+        Self {
+            name: item.name(),
+            display_mode: item.display_mode(),
+            status: item.status().to_string(),
+        }
+    }
+}
+
+/// Items added to every status bar, which can't be removed since the others are managed with them
+const CORE_ITEMS: [&str; 7] = [
+    "BuiltinAdd",
+    "BuiltinRemove",
+    "BuiltinList",
+    "BuiltinMove",
+    "Set",
+    "Help",
+    "Palette",
+];
+
+/// Changes to the layout of the status bar, which are deferred until no item is being processed,
+/// so that the indexes of items which are swapped out remain valid
+enum ItemChange {
+    Remove(String),
+    Move(String, usize),
+}
+
+/// Text drawn on the status bar, along with its colour
+type BarText = (String, Color);
+
+/// Something drawn on the status bar, which is kept on a single row
+struct BarEntry {
+    name: Option<BarText>,
+    status: Option<BarText>,
+    display_mode: DisplayMode,
+    /// Whether the blinking buffer cursor is drawn after the entry
+    cursor: bool,
+    separator: bool,
+}
+
+impl BarEntry {
+    /// The name and status to draw, where a collapsed entry is drawn as if it were name-only
+    fn texts(&self, collapsed: bool) -> (Option<BarText>, Option<&BarText>) {
+        match (&self.display_mode, collapsed) {
+            (DisplayMode::Normal, false) => (
+                self.name
+                    .as_ref()
+                    .map(|(name, colour)| (format!("{name} ::"), *colour)),
+                self.status.as_ref(),
+            ),
+            (DisplayMode::Normal | DisplayMode::NameOnly, _) => (
+                self.name.as_ref().map(|(name, colour)| {
+                    (
+                        name.clone(),
+                        self.status.as_ref().map_or(*colour, |(_, colour)| *colour),
+                    )
+                }),
+                None,
+            ),
+            (DisplayMode::StatusOnly, _) => (None, self.status.as_ref()),
+            (DisplayMode::None, _) => (None, None),
+        }
+    }
+
+    fn collapsible(&self) -> bool {
+        matches!(self.display_mode, DisplayMode::Normal)
+            && self.status.as_ref().is_some_and(|(x, _)| !x.is_empty())
+    }
+
+    fn status_width(&self, drawing: &DrawingSettings, font_size: f32) -> f32 {
+        self.status
+            .as_ref()
+            .map_or(0.0, |(x, _)| drawing.measure_text(x, font_size).0)
+    }
+
+    fn width(&self, drawing: &DrawingSettings, font_size: f32, collapsed: bool) -> f32 {
+        let (name, status) = self.texts(collapsed);
+        let mut width = 0.0;
+        if let Some((name, _)) = &name {
+            width += drawing.measure_text(name, font_size).0;
+            if status.is_some() {
+                width += 8.0;
+            }
+        }
+        if let Some((status, _)) = status {
+            width += drawing.measure_text(status, font_size).0;
+        }
+        if self.cursor {
+            width += 3.0 + get_normal_line_width();
+        }
+        if self.separator {
+            width += 32.0;
+        }
+        width
+    }
+
+    fn draw(
+        &self,
+        drawing: &DrawingSettings,
+        (mut x, y): (f32, f32),
+        row_height: f32,
+        font_size: f32,
+        (collapsed, draw_cursor): (bool, bool),
+    ) {
+        let (name, status) = self.texts(collapsed);
+        if let Some((name, colour)) = &name {
+            x += draw_text_in_bounds(
+                drawing,
+                name,
+                x,
+                y,
+                font_size,
+                *colour,
+                (None, Some(row_height)),
+            )
+            .0;
+            if status.is_some() {
+                x += 8.0;
+            }
+        }
+        if let Some((status, colour)) = status {
+            x += draw_text_in_bounds(
+                drawing,
+                status,
+                x,
+                y,
+                font_size,
+                *colour,
+                (None, Some(row_height)),
+            )
+            .0;
+        }
+        if self.cursor {
+            x += 3.0;
+            if let (true, Some((_, colour))) = (draw_cursor, &name) {
+                let line_padding = row_height * 0.25;
+                draw_line(
+                    x,
+                    y + line_padding,
+                    x,
+                    y + row_height - line_padding,
+                    get_normal_line_width(),
+                    *colour,
+                );
+            }
+            x += get_normal_line_width();
+        }
+        if self.separator {
+            x += 16.0;
+            draw_line(
+                x,
+                y,
+                x,
+                y + row_height,
+                get_normal_line_width(),
+                drawing.colour(AppColour::StatusBarSeparator),
+            );
+        }
+    }
+}
+
+#[derive(Debug)]
+struct QueuedCommand {
+    command: String,
+    id: Option<u64>,
+    expand_aliases: bool,
+    condition: Condition,
+}
+
+/// A command whose item was still waiting after it ran, which is polled until it finishes
+#[derive(Debug)]
+struct RunningCommand {
+    name: String,
+    id: Option<u64>,
+    /// The position of the command in the order commands were run, so that `&&` and `||` only
+    /// wait for the command before them
+    serial: u64,
 }
 
 pub struct StatusBar<'a> {
     time_started: Instant,
     items: Vec<Box<dyn Item>>,
+    registry: Registry,
     pub buffer: String,
     pub drawing: &'a DrawingSettings,
-    commands_queue: VecDeque<String>,
-    current_command: Option<String>,
+    commands_queue: VecDeque<QueuedCommand>,
+    running_commands: Vec<RunningCommand>,
+    commands_run: u64,
+    last_command_ok: bool,
+    command_results: HashMap<u64, CommandResult>,
+    next_command_id: u64,
     command_history: Vec<String>,
     command_history_offset: usize,
+    taken_items: Vec<(usize, ItemSummary)>,
+    item_changes: Vec<ItemChange>,
+    aliases: Vec<(String, Shorthand)>,
+    variables: HashMap<String, String>,
+    completion: Option<Completion>,
+    scroll_offset: usize,
 }
 
 impl<'a> StatusBar<'a> {
     pub fn new(drawing: &'a DrawingSettings) -> Self {
         Self {
             time_started: Instant::now(),
-            items: vec![Box::<Add>::default()],
+            items: vec![
+                Box::<Add>::default(),
+                Box::<BuiltinRemove>::default(),
+                Box::<BuiltinList>::default(),
+                Box::<BuiltinMove>::default(),
+                Box::<Set>::default(),
+                Box::<Help>::default(),
+                Box::<Palette>::default(),
+            ],
+            registry: Registry::default(),
             buffer: String::new(),
             drawing,
             commands_queue: VecDeque::new(),
-            current_command: None,
+            running_commands: Vec::new(),
+            commands_run: 0,
+            last_command_ok: true,
+            command_results: HashMap::new(),
+            next_command_id: 0,
             command_history: Vec::default(),
             command_history_offset: 0,
+            taken_items: Vec::new(),
+            item_changes: Vec::new(),
+            aliases: Vec::new(),
+            variables: HashMap::new(),
+            completion: None,
+            scroll_offset: 0,
         }
     }
 
-    pub fn add<T>(&mut self)
-    where
-        T: Item + Default + 'static,
-    {
-        self.items.push(Box::<T>::default());
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn registry_mut(&mut self) -> &mut Registry {
+        &mut self.registry
+    }
+
+    /// Adds the registered item with `name` to the end of the status bar
+    pub fn add_item(&mut self, game: &mut SudokuGame, name: &str) -> Result<(), String> {
+        let factory = self
+            .registry
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("could not find item '{name}'"))?;
+
+        if factory.singleton && self.summary_index_with_name(&factory.name).is_some() {
+            return Err(format!("'{}' has already been added", factory.name));
+        }
+
+        match factory.source {
+            ItemSource::Builtin(create) => self.items.push(create()),
+            ItemSource::Command(command) => {
+                let result = self.run_command(game, &command);
+                if !matches!(&result.status[..], "Ok" | "Waiting") || !result.buffer.is_empty() {
+                    return Err(format!(
+                        "'{}' failed to load: {} {}",
+                        factory.name, result.status, result.buffer
+                    ));
+                }
+            }
+        }
+
+        Ok(())
     }
 
     pub fn items(&self) -> impl Iterator<Item = &dyn Item> {
@@ -192,6 +531,125 @@ impl<'a> StatusBar<'a> {
         None
     }
 
+    /// Swaps the item at `idx` with a dummy, so that it can be called with access to the status bar
+    fn take_item(&mut self, idx: usize) -> Option<Box<dyn Item>> {
+        let item = self.items.get_mut(idx)?;
+        let mut dummy_item: Box<dyn Item + 'static> = Box::<Dummy>::default();
+        std::mem::swap(item, &mut dummy_item);
+        self.taken_items
+            .push((idx, ItemSummary::of(dummy_item.as_mut())));
+        Some(dummy_item)
+    }
+
+    fn return_item(&mut self, idx: usize, item: Box<dyn Item>) {
+        self.taken_items.retain(|(i, _)| *i != idx);
+        self.items[idx] = item;
+    }
+
+    /// Summaries of every item, including those which are currently being called
+    pub fn item_summaries(&mut self) -> Vec<ItemSummary> {
+        let mut summaries = self
+            .items
+            .iter_mut()
+            .map(|x| ItemSummary::of(x.as_mut()))
+            .collect::<Vec<_>>();
+
+        for (idx, summary) in &self.taken_items {
+            summaries[*idx] = summary.clone();
+        }
+
+        summaries
+    }
+
+    /// Generates a board string with the first item which provides the board generator `name`.
+    /// Items which are running (other than the item asking for the board) are swapped for dummies,
+    /// so their generators can't be found, which is reported as an error rather than as an unknown
+    /// generator
+    pub fn generate_board(&mut self, name: &str, seed: u32) -> Option<Result<String, String>> {
+        if let Some(result) = self
+            .items
+            .iter_mut()
+            .find_map(|x| x.generate_board(name, seed))
+        {
+            return Some(result);
+        }
+
+        let running = self
+            .taken_items
+            .split_last()
+            .map(|(_, running)| running)
+            .unwrap_or_default()
+            .iter()
+            .map(|(_, x)| x.name.clone())
+            .collect::<Vec<_>>();
+        (!running.is_empty()).then(|| {
+            Err(format!(
+                "not found, generators of running items ({}) can't be used until they return, \
+                 such as by using commands.run_async",
+                running.join(", ")
+            ))
+        })
+    }
+
+    fn summary_index_with_name(&mut self, name: &str) -> Option<usize> {
+        self.item_summaries()
+            .iter()
+            .position(|x| x.name.to_lowercase() == name.to_lowercase())
+    }
+
+    /// Whether the item with `name` is added to every status bar, and can't be removed
+    pub fn is_core_item(&self, name: &str) -> bool {
+        CORE_ITEMS.iter().any(|x| x.eq_ignore_ascii_case(name))
+    }
+
+    /// Removes the item with `name` once no items are being processed
+    pub fn remove_item(&mut self, name: &str) -> Result<(), String> {
+        match self.summary_index_with_name(name) {
+            Some(_) if self.is_core_item(name) => Err(format!("Cannot remove core item '{name}'")),
+            Some(_) => {
+                self.item_changes.push(ItemChange::Remove(name.to_string()));
+                Ok(())
+            }
+            None => Err(format!("No item with name '{name}'")),
+        }
+    }
+
+    /// Moves the item with `name` to `index` once no items are being processed
+    pub fn move_item(&mut self, name: &str, index: usize) -> Result<(), String> {
+        match self.summary_index_with_name(name) {
+            Some(0) => Err("Cannot move BuiltinAdd".to_string()),
+            Some(_) if index == 0 || index >= self.items.len() => {
+                Err(format!("Index {index} is out of bounds"))
+            }
+            Some(_) => {
+                self.item_changes
+                    .push(ItemChange::Move(name.to_string(), index));
+                Ok(())
+            }
+            None => Err(format!("No item with name '{name}'")),
+        }
+    }
+
+    fn apply_item_changes(&mut self) {
+        for change in std::mem::take(&mut self.item_changes) {
+            match change {
+                ItemChange::Remove(name) => {
+                    if let Some(idx) = self.index_with_name(&name) {
+                        debug!("Removing item '{name}' from status bar");
+                        self.items.remove(idx);
+                    }
+                }
+                ItemChange::Move(name, index) => {
+                    if let Some(idx) = self.index_with_name(&name) {
+                        debug!("Moving item '{name}' to index {index}");
+                        let item = self.items.remove(idx);
+                        self.items.insert(index.min(self.items.len()), item);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn restart(&mut self, game: &mut SudokuGame) {
         let span = span!(Level::INFO, "BoardInit");
         let _enter = span.enter();
@@ -200,34 +658,218 @@ impl<'a> StatusBar<'a> {
 
         let len = self.items.len();
         for idx in 0..len {
-            let mut dummy_item: Box<dyn Item + 'static> = Box::<Dummy>::default();
-
-            let Some(item) = self.items.get_mut(idx) else {
+            let Some(mut item) = self.take_item(idx) else {
                 continue;
             };
 
-            std::mem::swap(item, &mut dummy_item);
-            let mut item = dummy_item;
             item.board_init(game, self);
 
-            *self.items.get_mut(idx).unwrap() = item;
+            self.return_item(idx, item);
         }
         self.buffer = buffer;
     }
 
+    pub fn run_key_hooks(&mut self, game: &mut SudokuGame) {
+        let span = span!(Level::TRACE, "KeyHooks");
+        let _enter = span.enter();
+
+        game.input.key_consumed = false;
+        let Some(key) = get_last_key_pressed() else {
+            return;
+        };
+
+        for idx in 0..self.items.len() {
+            let Some(mut item) = self.take_item(idx) else {
+                continue;
+            };
+
+            let action = item.key_hook(game, self, key);
+            if let HookAction::Stop = action {
+                trace!("Key {:?} consumed by '{}'", key, item.name());
+                game.input.key_consumed = true;
+            }
+
+            self.return_item(idx, item);
+            if game.input.key_consumed {
+                break;
+            }
+        }
+    }
+
     fn should_draw_buffer_line(&self) -> bool {
         let duration = Instant::now().duration_since(self.time_started);
         let duration_secs = duration.as_secs_f32();
         let num_half_secs = duration_secs / 0.5;
         let whole_num_half_secs = num_half_secs as u32;
-        whole_num_half_secs % 2 == 0
+        whole_num_half_secs.is_multiple_of(2)
     }
 
     fn buffer_entered(&mut self, game: &mut SudokuGame) -> Option<String> {
+        let og_command = self.buffer.clone();
+        let name = self.run_buffer(game)?;
+        self.remember_command(og_command);
+        Some(name)
+    }
+
+    /// Moves `command` to the end of the command history
+    fn remember_command(&mut self, command: String) {
+        self.command_history.retain(|x| *x != command);
+        self.command_history.push(command);
+    }
+
+    /// What `command` expands into, if it matches an alias
+    fn alias_expansion(&self, command: &str) -> Option<String> {
+        let expanded = self
+            .aliases
+            .iter()
+            .find_map(|(_, x)| x.apply_to_string(command.trim()))?;
+        trace!("Expanded alias '{}' into '{}'", command, expanded);
+        Some(expanded)
+    }
+
+    /// Replaces the alias command at the front of the queue with the commands it expands into,
+    /// returning whether it was an alias. Expansions aren't expanded again, so that an alias can't
+    /// loop
+    fn expand_queued_alias(&mut self) -> bool {
+        let Some(expanded) = self
+            .commands_queue
+            .front()
+            .filter(|x| x.expand_aliases)
+            .and_then(|x| self.alias_expansion(&x.command))
+        else {
+            return false;
+        };
+
+        let queued = self.commands_queue.pop_front().unwrap();
+        self.remember_command(queued.command);
+
+        let commands = command::expand_alias(&expanded, queued.condition);
+        if commands.is_empty() {
+            self.finish_command(queued.id, "Ok".to_string());
+        }
+        for (i, command) in commands.into_iter().enumerate().rev() {
+            self.commands_queue.push_front(QueuedCommand {
+                command: command.text,
+                id: if i == 0 { queued.id } else { None },
+                expand_aliases: false,
+                condition: command.condition,
+            });
+        }
+        true
+    }
+
+    /// Adds a user defined shorthand, which expands into one or more commands. `name` is the
+    /// pattern as the user wrote it
+    pub fn add_alias(&mut self, name: &str, alias: Shorthand) {
+        self.aliases.push((name.to_string(), alias));
+    }
+
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &Shorthand)> {
+        self.aliases.iter().map(|(name, alias)| (&name[..], alias))
+    }
+
+    /// Commands entered into the buffer, oldest first
+    pub fn command_history(&self) -> &[String] {
+        &self.command_history
+    }
+
+    /// Sets a variable which is substituted for `$name` in commands, or removes it if `value` is
+    /// `None`
+    pub fn set_variable(&mut self, name: &str, value: Option<String>) {
+        match value {
+            Some(value) => self.variables.insert(name.to_string(), value),
+            None => self.variables.remove(name),
+        };
+    }
+
+    pub fn variables(&self) -> &HashMap<String, String> {
+        &self.variables
+    }
+
+    /// Completes the word at the end of the buffer. If there are several candidates, the word is
+    /// extended as far as they agree, and after that, pressing Tab again cycles through them
+    fn complete_buffer(&mut self) {
+        if let Some(completion) = &mut self.completion {
+            if completion.current() == self.buffer {
+                completion.index = (completion.index + 1) % completion.candidates.len();
+                self.buffer = completion.current();
+                return;
+            }
+        }
+        self.completion = None;
+
+        let start = command::last_command_start(&self.buffer);
+        let word = self.buffer[start..]
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let before = self.buffer[..self.buffer.len() - word.len()].to_string();
+
+        let args = before[start..].split_whitespace().collect::<Vec<_>>();
+        let candidates = match args.split_first() {
+            None => self.command_completions(&word),
+            Some((name, args)) => self
+                .index_with_name(name)
+                .map(|idx| self.items[idx].completions(self, args))
+                .unwrap_or_default(),
+        };
+
+        let candidates = completion::matching(&word, candidates);
+        trace!("Completing '{}' with: {:?}", word, candidates);
+        match candidates.as_slice() {
+            [] => {}
+            [candidate] => self.buffer = format!("{before}{candidate} "),
+            _ => {
+                let prefix = completion::common_prefix(&candidates);
+                if prefix.chars().count() > word.chars().count() {
+                    self.buffer = format!("{before}{prefix}");
+                } else {
+                    let completion = Completion {
+                        before,
+                        candidates,
+                        index: 0,
+                    };
+                    self.buffer = completion.current();
+                    self.completion = Some(completion);
+                }
+            }
+        }
+    }
+
+    /// Candidates for the name of a command, which are the names of loaded items and the items
+    /// which can be added, along with the completions of any item shorthand which `word` uses
+    fn command_completions(&self, word: &str) -> Vec<String> {
+        let mut candidates = self
+            .items
+            .iter()
+            .map(|x| x.name())
+            .chain(self.registry.iter().map(|x| x.name.clone()))
+            .collect::<Vec<_>>();
+
+        for item in &self.items {
+            let Some(expanded) = item.shorthands().and_then(|x| x.apply_to_string(word)) else {
+                continue;
+            };
+
+            if let Some(prefix) = word.strip_suffix(&expanded[..]) {
+                candidates.extend(
+                    item.completions(self, &[])
+                        .into_iter()
+                        .map(|x| format!("{prefix}{x}")),
+                );
+            }
+        }
+
+        candidates
+    }
+
+    fn run_buffer(&mut self, game: &mut SudokuGame) -> Option<String> {
         let span = span!(Level::TRACE, "RunCommand");
         let _enter = span.enter();
 
-        let buffer = self.buffer.clone();
+        let raw_buffer = self.buffer.clone();
+        let buffer = command::substitute_variables(&raw_buffer, &self.variables);
         let og_command = buffer.clone();
 
         let mut command_words = buffer.split_whitespace();
@@ -236,7 +878,6 @@ impl<'a> StatusBar<'a> {
 
         let mut buffer = command_words.collect::<Vec<_>>().join(" ");
 
-        let mut dummy_item: Box<dyn Item + 'static> = Box::<Dummy>::default();
         let mut idx = self.index_with_name(command_name);
 
         if idx.is_none() {
@@ -261,10 +902,14 @@ impl<'a> StatusBar<'a> {
 
         let idx = idx?;
 
-        let item = self.items.get_mut(idx)?;
-
-        std::mem::swap(item, &mut dummy_item);
-        let mut item = dummy_item;
+        let mut item = self.take_item(idx)?;
+        if item.raw_arguments() && item.name().eq_ignore_ascii_case(command_name) {
+            buffer = raw_buffer
+                .split_whitespace()
+                .skip(1)
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
 
         let before = buffer.clone();
         self.buffer.clone_from(&buffer);
@@ -274,69 +919,221 @@ impl<'a> StatusBar<'a> {
             self.buffer.clear();
         }
 
-        if let Some((idx, _)) = self
-            .command_history
-            .iter()
-            .enumerate()
-            .find(|(_, text)| *text == &og_command)
-        {
-            self.command_history.remove(idx);
-            self.command_history.push(og_command);
-        } else {
-            self.command_history.push(og_command);
-        }
-        *self.items.get_mut(idx).unwrap() = item;
+        self.return_item(idx, item);
         Some(name_after.to_string()) // calling item.activate() could hypothetically result in a
                                      // changing of name
     }
 
+    /// Runs a single command immediately, rather than waiting for it to be taken from the queue.
+    /// If it's an alias, the commands it expands into are run one after another, with `&&` and `||`
+    /// checking the status the previous command returned with, and the result of the last command
+    /// which ran is returned
+    pub fn run_command(&mut self, game: &mut SudokuGame, command: &str) -> CommandResult {
+        let span = span!(Level::TRACE, "RunCommandNow");
+        let _enter = span.enter();
+
+        let buffer = std::mem::take(&mut self.buffer);
+        let result = match self.alias_expansion(command) {
+            Some(expanded) => {
+                self.remember_command(command.trim().to_string());
+                self.run_alias_now(game, &expanded)
+            }
+            None => self.run_command_now(game, command, true),
+        };
+        self.buffer = buffer;
+        result
+    }
+
+    fn run_alias_now(&mut self, game: &mut SudokuGame, expanded: &str) -> CommandResult {
+        let mut last_ok = true;
+        let mut result = CommandResult {
+            status: "Ok".to_string(),
+            buffer: String::new(),
+        };
+        for command in command::expand_alias(expanded, Condition::Always) {
+            if command.condition != Condition::Always
+                && last_ok != (command.condition == Condition::OnOk)
+            {
+                trace!("Skipping '{}' from alias", command.text);
+                continue;
+            }
+
+            result = self.run_command_now(game, &command.text, false);
+            last_ok = matches!(&result.status[..], "Ok" | "Waiting");
+        }
+        result
+    }
+
+    fn run_command_now(
+        &mut self,
+        game: &mut SudokuGame,
+        command: &str,
+        remember: bool,
+    ) -> CommandResult {
+        self.buffer = command.trim().to_string();
+        let cmd_name = if remember {
+            self.buffer_entered(game)
+        } else {
+            self.run_buffer(game)
+        };
+        let status = match cmd_name {
+            Some(cmd_name) => match self.item_with_name(&cmd_name) {
+                Some(item) => item.status().to_string(),
+                None => "ChangedCommandName".to_string(),
+            },
+            None => "BadCommand".to_string(),
+        };
+
+        CommandResult {
+            status,
+            buffer: std::mem::take(&mut self.buffer),
+        }
+    }
+
+    /// Queues a command like `enter_buffer_commands`, with its result being available from
+    /// `take_command_result` once the command is no longer waiting
+    pub fn enter_tracked_buffer_command(&mut self, command: &str) -> u64 {
+        let id = self.next_command_id;
+        self.next_command_id += 1;
+
+        trace!("Adding tracked command to queue: {command} (id = {id})");
+        self.commands_queue.push_back(QueuedCommand {
+            command: command.trim().to_string(),
+            id: Some(id),
+            expand_aliases: true,
+            condition: Condition::Always,
+        });
+        id
+    }
+
+    pub fn take_command_result(&mut self, id: u64) -> Option<CommandResult> {
+        self.command_results.remove(&id)
+    }
+
+    fn finish_command(&mut self, id: Option<u64>, status: String) {
+        if let Some(id) = id {
+            self.command_results.insert(
+                id,
+                CommandResult {
+                    status,
+                    buffer: self.buffer.clone(),
+                },
+            );
+        }
+    }
+
     fn process_queued_buffer_commands(&mut self, game: &mut SudokuGame) -> Result<(), String> {
         let span = span!(Level::TRACE, "QueuedCommands");
         let _enter = span.enter();
 
-        if let Some(current_command) = &self.current_command.clone() {
-            let span = span!(Level::TRACE, "Wait");
-            let _enter = span.enter();
+        for running in std::mem::take(&mut self.running_commands) {
+            let status = match self.item_with_name(&running.name) {
+                Some(item) => match item.status() {
+                    ItemStatus::Waiting => None,
+                    x => Some(x.to_string()),
+                },
+                None => Some("ChangedCommandName".to_string()),
+            };
 
-            if let Some(item) = self.item_with_name(current_command) {
-                let item_name = item.name();
-                match item.status() {
-                    // Commands won't wait for eachother, this
-                    // means the application loads faster, but I might in the future add a
-                    // syntactic way of specifying this behaviour
-                    ItemStatus::Waiting => {}
-                    x => {
-                        trace!(
-                            "Command with name '{}' finished with status: {}",
-                            item_name,
-                            x
-                        );
-                        self.current_command = None;
-                    }
-                };
+            let Some(status) = status else {
+                self.running_commands.push(running);
+                continue;
+            };
+
+            trace!(
+                "Command with name '{}' finished with status: {}",
+                running.name,
+                status
+            );
+            if running.serial == self.commands_run {
+                self.last_command_ok = status == "Ok";
             }
+            self.finish_command(running.id, status);
         }
-        while let Some(cmd) = self.commands_queue.pop_front() {
+
+        while self.commands_queue.front().is_some() {
+            if self.expand_queued_alias() {
+                continue;
+            }
+
+            let queued = self.commands_queue.front().unwrap();
+            match queued.condition {
+                // Commands separated by `;` won't wait for eachother, this means the application
+                // loads faster
+                Condition::Always => {}
+                _ if self
+                    .running_commands
+                    .iter()
+                    .any(|x| x.serial == self.commands_run) =>
+                {
+                    return Ok(())
+                }
+                condition if self.last_command_ok != (condition == Condition::OnOk) => {
+                    trace!(
+                        "Skipping '{}', previous command status doesn't match {:?}",
+                        queued.command,
+                        condition
+                    );
+                    let id = queued.id;
+                    self.commands_queue.pop_front();
+                    self.finish_command(id, "Skipped".to_string());
+                    continue;
+                }
+                _ => {}
+            }
+
+            let Some(QueuedCommand {
+                command: cmd,
+                id,
+                expand_aliases,
+                ..
+            }) = self.commands_queue.pop_front()
+            else {
+                break;
+            };
+
             let span = span!(Level::TRACE, "Run");
             let _enter = span.enter();
 
             trace!("Attempting to run: '{}'", cmd);
+            self.commands_run += 1;
             self.buffer = cmd.to_string();
-            if let Some(cmd_name) = self.buffer_entered(game) {
+            let cmd_name = if expand_aliases {
+                self.buffer_entered(game)
+            } else {
+                self.run_buffer(game)
+            };
+            self.last_command_ok = false;
+            if let Some(cmd_name) = cmd_name {
                 trace!("Ran command with name '{}'", cmd_name);
-                self.current_command = Some(cmd_name.clone());
                 if let Some(item) = self.item_with_name(&cmd_name) {
                     match item.status() {
-                        ItemStatus::Err => Err(cmd_name)?,
-                        ItemStatus::Waiting => return Ok(()),
-                        ItemStatus::Ok(_) => continue,
+                        ItemStatus::Err => {
+                            self.finish_command(id, ItemStatus::Err.to_string());
+                            Err(cmd_name)?;
+                        }
+                        ItemStatus::Waiting => {
+                            self.running_commands.push(RunningCommand {
+                                name: cmd_name,
+                                id,
+                                serial: self.commands_run,
+                            });
+                            return Ok(());
+                        }
+                        ItemStatus::Ok(_) => {
+                            self.last_command_ok = true;
+                            self.finish_command(id, "Ok".to_string());
+                            continue;
+                        }
                     };
                 } else {
                     warn!("Unable to query status of command: '{}'", cmd_name);
+                    self.finish_command(id, "ChangedCommandName".to_string());
                     return Err(format!("ChangedCommandName: {cmd_name}"))?;
                 }
             } else {
                 error!("Unable to find handler for command: '{}'", cmd);
+                self.finish_command(id, "BadCommand".to_string());
                 return Err(format!("BadCommand: {cmd}"))?;
             }
         }
@@ -350,9 +1147,12 @@ impl<'a> StatusBar<'a> {
 
         let mut commands = commands
             .iter()
-            .flat_map(|x| {
-                x.lines()
-                    .flat_map(|y| y.split('&').map(|z| z.trim().to_string()))
+            .flat_map(|x| command::parse_commands(x))
+            .map(|command| QueuedCommand {
+                command: command.text,
+                id: None,
+                expand_aliases: true,
+                condition: command.condition,
             })
             .collect::<VecDeque<_>>();
 
@@ -366,6 +1166,7 @@ impl<'a> StatusBar<'a> {
     }
 
     pub fn draw(&mut self, game: &mut SudokuGame, drawing: &DrawingSettings) {
+        scripts::next_frame();
         self.process_inputs(game);
         self.render(game, drawing);
     }
@@ -374,6 +1175,8 @@ impl<'a> StatusBar<'a> {
         let span = span!(Level::INFO, "ProcessStatusBar");
         let _enter = span.enter();
 
+        self.apply_item_changes();
+
         if let Err(message) = self.process_queued_buffer_commands(game) {
             self.buffer = message;
         };
@@ -414,6 +1217,7 @@ impl<'a> StatusBar<'a> {
                 game.reset_signalled = ResetSignal::Hard;
                 return;
             }
+            Some(InputAction::CompleteBuffer) => self.complete_buffer(),
             Some(InputAction::UpBuffer) => {
                 should_reset_history_pos = false;
                 if self.command_history_offset < self.command_history.len() {
@@ -475,11 +1279,7 @@ impl<'a> StatusBar<'a> {
     }
 
     fn process_item(&mut self, raw_idx: usize, i: &mut u8, game: &mut SudokuGame) {
-        let mut dummy_item: Box<dyn Item + 'static> = Box::<Dummy>::default();
-
-        let item = self.items.get_mut(raw_idx).unwrap();
-        std::mem::swap(item, &mut dummy_item);
-        let mut item = dummy_item;
+        let mut item = self.take_item(raw_idx).unwrap();
 
         let display_mode = item.display_mode();
         let display = !matches!(display_mode, DisplayMode::None);
@@ -511,184 +1311,214 @@ impl<'a> StatusBar<'a> {
             *i += 1;
         }
 
-        self.items[raw_idx] = item;
+        self.return_item(raw_idx, item);
     }
 
     fn render(&mut self, game: &mut SudokuGame, drawing: &DrawingSettings) {
         let span = span!(Level::INFO, "RenderStatusBar");
         let _enter = span.enter();
 
-        let (width, height) = screen_size();
-        let status_bar_height = get_status_bar_height();
+        let width = screen_width();
+        let row_height = get_status_bar_row_height();
+        let font_size = row_height * 0.9;
+        let mut layout = drawing.status_bar_layout();
+
+        let mut entries = self.item_entries(game, drawing);
+        let buffer_entry = entries.len();
+        entries.extend(self.buffer_entries(game, drawing));
+
+        // Entries are first wrapped onto more rows, then collapsed to their names, and if they
+        // still don't fit, the status bar is scrolled
+        let mut available = width - 40.0;
+        let mut collapsed = vec![false; entries.len()];
+        let entry_widths = |collapsed: &[bool]| {
+            entries
+                .iter()
+                .zip(collapsed)
+                .map(|(x, collapsed)| x.width(drawing, font_size, *collapsed))
+                .collect::<Vec<_>>()
+        };
+        let mut rows = pack_rows(&entry_widths(&collapsed), available);
+
+        if layout.collapse && rows.len() > layout.max_rows {
+            let mut order = (0..entries.len())
+                .filter(|x| entries[*x].collapsible())
+                .collect::<Vec<_>>();
+            order.sort_by(|a, b| {
+                let width = |x: usize| entries[x].status_width(drawing, font_size);
+                width(*b).total_cmp(&width(*a))
+            });
+
+            for idx in order {
+                collapsed[idx] = true;
+                rows = pack_rows(&entry_widths(&collapsed), available);
+                if rows.len() <= layout.max_rows {
+                    break;
+                }
+            }
+        }
 
-        let (start_x, start_y) = (0.0, height - status_bar_height);
-        let (bar_width, bar_height) = (width, status_bar_height);
+        let scroll_width = drawing.measure_text("[0/0]", font_size).0 + 16.0;
+        if rows.len() > layout.max_rows {
+            available -= scroll_width;
+            rows = pack_rows(&entry_widths(&collapsed), available);
+        }
+
+        let visible_rows = rows.len().clamp(1, layout.max_rows.max(1));
+        let max_scroll = rows.len() - visible_rows;
+        layout.rows = visible_rows;
+        drawing.set_status_bar_layout(layout);
+
+        let (start_x, start_y) = layout.origin();
+        let bar_height = row_height * visible_rows as f32;
+        let (mouse_x, mouse_y) = mouse_position();
+        if mouse_x >= start_x && mouse_y >= start_y && mouse_y < start_y + bar_height {
+            match mouse_wheel().1 {
+                y if y > 0.0 => self.scroll_offset = self.scroll_offset.saturating_sub(1),
+                y if y < 0.0 => self.scroll_offset += 1,
+                _ => {}
+            }
+        }
+
+        // Whilst typing, the row with the buffer must be visible
+        if game.input.enter_buffer {
+            if let Some(row) = rows.iter().position(|x| x.contains(&buffer_entry)) {
+                self.scroll_offset = self
+                    .scroll_offset
+                    .clamp((row + 1).saturating_sub(visible_rows), row);
+            }
+        }
+        self.scroll_offset = self.scroll_offset.min(max_scroll);
 
         draw_rectangle(
             start_x,
             start_y,
-            bar_width,
+            width,
             bar_height,
             drawing.colour(AppColour::StatusBar),
         );
 
-        let mut cursor_x = 20.0;
+        let widths = entry_widths(&collapsed);
+        for (row_idx, row) in rows
+            .into_iter()
+            .skip(self.scroll_offset)
+            .take(visible_rows)
+            .enumerate()
+        {
+            let row_y = start_y + row_idx as f32 * row_height;
+            let mut cursor_x = if layout.align_right {
+                width - 20.0 - widths[row.clone()].iter().sum::<f32>()
+            } else {
+                20.0
+            };
+            if layout.align_right && max_scroll != 0 {
+                cursor_x -= scroll_width;
+            }
 
-        let font_size = status_bar_height * 0.9;
-        let cursor_y = start_y;
-        let mut visible_index = 1;
-        for i in 0..self.items.len() {
-            self.render_item(
-                (i, &mut visible_index),
-                game,
+            for idx in row {
+                entries[idx].draw(
+                    drawing,
+                    (cursor_x, row_y),
+                    row_height,
+                    font_size,
+                    (collapsed[idx], self.should_draw_buffer_line()),
+                );
+                cursor_x += widths[idx];
+            }
+        }
+
+        if max_scroll != 0 {
+            draw_text_in_bounds(
                 drawing,
-                (&mut cursor_x, cursor_y),
-                font_size,
+                &format!("[{}/{}]", self.scroll_offset + 1, max_scroll + 1),
+                width - scroll_width,
                 start_y,
+                font_size,
+                drawing.colour(AppColour::StatusBarItem),
+                (None, Some(row_height)),
             );
         }
 
-        // Now that each status bar item has been drawn, we can start to draw the buffer input
+        for item in &self.items {
+            item.overlay_draw_hook(drawing);
+        }
+    }
+
+    /// Updates every item, returning an entry for each item which is displayed
+    fn item_entries(&mut self, game: &mut SudokuGame, drawing: &DrawingSettings) -> Vec<BarEntry> {
+        let mut entries = vec![];
+        let mut visible_index = 1;
+        for idx in 0..self.items.len() {
+            let mut item = self.take_item(idx).unwrap();
+            let display_mode = item.display_mode();
+            let (text, color) = item.update(game, self);
+            if !matches!(display_mode, DisplayMode::None) {
+                let name_colour = if InputAction::is_function_down(
+                    visible_index,
+                    InputActionContext::Generic,
+                    &game.input,
+                ) {
+                    drawing.colour(AppColour::StatusBarItemSelected)
+                } else {
+                    drawing.colour(AppColour::StatusBarItem)
+                };
+                visible_index += 1;
+
+                entries.push(BarEntry {
+                    name: matches!(display_mode, DisplayMode::Normal | DisplayMode::NameOnly)
+                        .then(|| (item.name(), name_colour)),
+                    status: Some((text, color)),
+                    display_mode,
+                    cursor: false,
+                    separator: true,
+                });
+            }
+            self.return_item(idx, item);
+        }
+        entries
+    }
 
+    /// Entries for the buffer, and the command queue if there is one
+    fn buffer_entries(&self, game: &SudokuGame, drawing: &DrawingSettings) -> Vec<BarEntry> {
         let color = if game.input.enter_buffer {
             drawing.colour(AppColour::StatusBarBufferEdit)
         } else {
             drawing.colour(AppColour::StatusBarItem)
         };
 
-        let bounds = draw_text_in_bounds(
-            drawing,
-            &format!("> {}", self.buffer),
-            cursor_x,
-            cursor_y,
-            font_size,
-            color,
-            (None, Some(status_bar_height)),
-        );
-
-        cursor_x += bounds.0 + 3.0;
+        let mut entries = vec![BarEntry {
+            name: Some((format!("> {}", self.buffer), color)),
+            status: None,
+            display_mode: DisplayMode::NameOnly,
+            cursor: true,
+            separator: true,
+        }];
 
-        if self.should_draw_buffer_line() {
-            let line_padding = status_bar_height * 0.25;
-            draw_line(
-                cursor_x,
-                start_y + line_padding,
-                cursor_x,
-                height - line_padding,
-                get_normal_line_width(),
-                color,
-            );
-        }
-
-        cursor_x += get_normal_line_width() + 16.0;
-
-        draw_line(
-            cursor_x,
-            start_y,
-            cursor_x,
-            height,
-            get_normal_line_width(),
-            drawing.colour(AppColour::StatusBarSeparator),
+        let mut commands_queue = self
+            .commands_queue
+            .iter()
+            .map(|x| x.command.clone())
+            .collect::<Vec<_>>();
+        commands_queue.extend(
+            self.running_commands
+                .iter()
+                .rev()
+                .map(|x| format!("{}...", x.name)),
         );
-
-        cursor_x += 16.0;
-
-        // Next, if there's a command queue, we need to display it
-        let mut commands_queue = self.commands_queue.iter().cloned().collect::<Vec<_>>();
-        if let Some(active_command) = self.current_command.as_ref() {
-            commands_queue.push(format!("{active_command}..."));
-        }
         commands_queue.reverse();
 
-        let queue_string = format!("{}: [{}]", commands_queue.len(), commands_queue.join(", "));
         if !commands_queue.is_empty() {
-            let _gobounds = draw_text_in_bounds(
-                drawing,
-                &queue_string,
-                cursor_x,
-                cursor_y,
-                font_size,
-                drawing.colour(AppColour::StatusBarItem),
-                (None, Some(status_bar_height)),
-            );
-
-            //cursor_x += bounds.0 + 3.0;
-        }
-    }
-
-    #[allow(clippy::needless_pass_by_value)]
-    fn render_item(
-        &mut self,
-        index: (usize, &mut u32),
-        game: &mut SudokuGame,
-        drawing: &DrawingSettings,
-        cursor: (&mut f32, f32),
-        font_size: f32,
-        start_y: f32,
-    ) {
-        let status_bar_height = get_status_bar_height();
-        let height = screen_height();
-
-        let item = self.items.get_mut(index.0).unwrap();
-        let mut dummy_item: Box<dyn Item + 'static> = Box::<Dummy>::default();
-        let display_mode = item.display_mode();
-        std::mem::swap(item, &mut dummy_item);
-        let mut item = dummy_item;
-        if matches!(item.display_mode(), DisplayMode::None) {
-            let _ = item.update(game, self);
-            self.items[index.0] = item;
-            return;
-        }
-        let font_color = if InputAction::is_function_down(
-            *index.1 as u8,
-            InputActionContext::Generic,
-            &game.input,
-        ) {
-            drawing.colour(AppColour::StatusBarItemSelected)
-        } else {
-            drawing.colour(AppColour::StatusBarItem)
-        };
-        *index.1 += 1;
-        let (text, color) = item.update(game, self);
-        if matches!(display_mode, DisplayMode::Normal | DisplayMode::NameOnly) {
-            let (suffix, font_color) = if let DisplayMode::Normal = display_mode {
-                (" ::", font_color)
-            } else {
-                ("", color)
-            };
-            let bounds = draw_text_in_bounds(
-                drawing,
-                &format!("{}{}", item.name(), suffix),
-                *cursor.0,
-                cursor.1,
-                font_size,
-                font_color,
-                (None, Some(status_bar_height)),
-            );
-            *cursor.0 += bounds.0 + 8.0;
+            entries.push(BarEntry {
+                name: Some((
+                    format!("{}: [{}]", commands_queue.len(), commands_queue.join(", ")),
+                    drawing.colour(AppColour::StatusBarItem),
+                )),
+                status: None,
+                display_mode: DisplayMode::NameOnly,
+                cursor: false,
+                separator: false,
+            });
         }
-        if matches!(display_mode, DisplayMode::Normal | DisplayMode::StatusOnly) {
-            let bounds = draw_text_in_bounds(
-                drawing,
-                &text,
-                *cursor.0,
-                cursor.1,
-                font_size,
-                color,
-                (None, Some(status_bar_height)),
-            );
-            *cursor.0 += bounds.0;
-        }
-        *cursor.0 += 16.0;
-        draw_line(
-            *cursor.0,
-            start_y,
-            *cursor.0,
-            height,
-            get_normal_line_width(),
-            drawing.colour(AppColour::StatusBarSeparator),
-        );
-        *cursor.0 += 16.0;
-        self.items[index.0] = item;
+        entries
     }
 }
@@ -12,6 +12,10 @@ impl Item for OnBoardInit {
         "OnBoardInit".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("OnBoardInit [command]".to_string())
+    }
+
     fn activated(
         &mut self,
         _game: &mut crate::sudoku_game::SudokuGame,
@@ -8,6 +8,10 @@ impl Item for Padding {
         "Padding".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("Padding [start] [target] [speed]".to_string())
+    }
+
     fn activated(
         &mut self,
         game: &mut crate::sudoku_game::SudokuGame,
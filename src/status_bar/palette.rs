@@ -0,0 +1,334 @@
+use macroquad::{
+    miniquad::KeyCode,
+    shapes::draw_rectangle,
+    window::{screen_height, screen_width},
+};
+use tracing::{debug, span, Level};
+
+use crate::{
+    draw_helper::{draw_text_in_bounds, get_status_bar_row_height, AppColour, DrawingSettings},
+    input_helper::InputAction,
+    sudoku_game::SudokuGame,
+};
+
+use super::{completion, HookAction, Item, StatusBar};
+
+const PALETTE_KEY: KeyCode = KeyCode::Slash;
+
+/// How many of the most recent commands are offered
+const HISTORY_ENTRIES: usize = 20;
+
+struct Entry {
+    command: String,
+    description: String,
+    /// Whether the command is put into the buffer for arguments to be typed, rather than run
+    takes_arguments: bool,
+}
+
+/// A full-screen list of everything which can be run, filtered by typing
+#[derive(Default)]
+pub struct Palette {
+    open: bool,
+    query: String,
+    selected: usize,
+    entries: Vec<Entry>,
+    /// Indices into `entries` which match the query, best match first
+    matches: Vec<usize>,
+}
+
+/// Scores how well `query` matches `text` as a subsequence, ignoring case and whitespace in the
+/// query, or `None` if it doesn't match. Consecutive characters and characters at the start of
+/// words score higher, and shorter texts win ties
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text = text.chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut start = 0;
+    let mut last_match = None;
+    for c in query.chars().filter(|x| !x.is_whitespace()) {
+        let idx = (start..text.len()).find(|&i| text[i].to_lowercase().eq(c.to_lowercase()))?;
+
+        score += 10;
+        if idx > 0 && last_match == Some(idx - 1) {
+            score += 50;
+        }
+        if idx == 0
+            || !text[idx - 1].is_alphanumeric()
+            || (text[idx].is_uppercase() && text[idx - 1].is_lowercase())
+        {
+            score += 30;
+        }
+
+        last_match = Some(idx);
+        start = idx + 1;
+    }
+    Some(score - text.len() as i32)
+}
+
+impl Palette {
+    fn entries(status_bar: &StatusBar) -> Vec<Entry> {
+        let mut entries = status_bar
+            .command_history()
+            .iter()
+            .rev()
+            .take(HISTORY_ENTRIES)
+            .map(|x| Entry {
+                command: x.clone(),
+                description: "Recent command".to_string(),
+                takes_arguments: false,
+            })
+            .collect::<Vec<_>>();
+
+        let registry = status_bar.registry();
+        entries.extend(status_bar.items().map(|item| {
+            let name = item.name();
+            let factory = registry.get(&name);
+            let usage = factory.map(|x| x.usage.clone()).or_else(|| item.help());
+            let description = match (factory, &usage) {
+                (Some(factory), _) => format!("{} - {}", factory.description, factory.usage),
+                (None, Some(usage)) => usage.clone(),
+                (None, None) => "Added item".to_string(),
+            };
+            Entry {
+                takes_arguments: usage.is_some_and(|x| x.trim() != name),
+                command: name,
+                description,
+            }
+        }));
+
+        entries.extend(
+            registry
+                .iter()
+                .filter(|x| !x.singleton || status_bar.index_with_name(&x.name).is_none())
+                .map(|x| Entry {
+                    command: format!("BuiltinAdd {}", x.name),
+                    description: format!("Adds {} - {}", x.name, x.description),
+                    takes_arguments: false,
+                }),
+        );
+
+        // Aliases given as regular expressions need arguments to match, so only prefixes are
+        // offered
+        entries.extend(
+            status_bar
+                .aliases()
+                .filter(|(name, _)| !name.starts_with('^'))
+                .map(|(name, alias)| Entry {
+                    command: name.to_string(),
+                    description: format!("Alias for {}", alias.format().trim_end_matches("$1")),
+                    takes_arguments: false,
+                }),
+        );
+
+        entries.extend(completion::script_files().into_iter().map(|x| Entry {
+            description: format!("Loads the script {x}"),
+            command: format!("Eval @{x}"),
+            takes_arguments: false,
+        }));
+
+        let mut seen = std::collections::HashSet::new();
+        entries.retain(|x| seen.insert(x.command.clone()));
+        entries
+    }
+
+    /// Orders the entries matching the query, with matches in commands before matches in
+    /// descriptions
+    fn filter(&mut self) {
+        let mut matches = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, x)| {
+                fuzzy_score(&self.query, &x.command)
+                    .or_else(|| fuzzy_score(&self.query, &x.description).map(|x| x - 1000))
+                    .map(|score| (idx, score))
+            })
+            .collect::<Vec<_>>();
+
+        if !self.query.is_empty() {
+            matches.sort_by_key(|(_, score)| -score);
+        }
+        self.matches = matches.into_iter().map(|(idx, _)| idx).collect();
+        self.selected = 0;
+    }
+
+    fn show(&mut self, game: &mut SudokuGame, status_bar: &StatusBar) {
+        self.open = true;
+        self.query.clear();
+        self.entries = Self::entries(status_bar);
+        self.filter();
+        game.input.overlay_open = true;
+    }
+
+    fn hide(&mut self, game: &mut SudokuGame) {
+        self.open = false;
+        self.entries.clear();
+        self.matches.clear();
+        game.input.overlay_open = false;
+    }
+}
+
+impl Item for Palette {
+    fn name(&self) -> String {
+        "Palette".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("Palette".to_string())
+    }
+
+    fn activated(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "PaletteActivated");
+        let _enter = span.enter();
+
+        self.show(game, status_bar);
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::None
+    }
+
+    fn key_hook(
+        &mut self,
+        game: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+        key: KeyCode,
+    ) -> HookAction<()> {
+        if !self.open {
+            if key == PALETTE_KEY && !game.input.enter_buffer {
+                self.show(game, status_bar);
+                return HookAction::Stop;
+            }
+            return HookAction::Continue(());
+        }
+
+        match key {
+            KeyCode::Escape => self.hide(game),
+            KeyCode::Enter => {
+                let entry = self.matches.get(self.selected).map(|x| &self.entries[*x]);
+                match entry {
+                    Some(entry) if entry.takes_arguments => {
+                        debug!("Typing '{}' from the palette", entry.command);
+                        status_bar.buffer = format!("{} ", entry.command);
+                        game.input.enter_buffer = true;
+                    }
+                    Some(entry) => {
+                        debug!("Running '{}' from the palette", entry.command);
+                        status_bar.enter_buffer_commands(&[&entry.command]);
+                    }
+                    None => {}
+                }
+                self.hide(game);
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.filter();
+            }
+            _ => {
+                if let Some(c) = InputAction::get_last_raw_char() {
+                    self.query.push(c);
+                    self.filter();
+                }
+            }
+        }
+        HookAction::Stop
+    }
+
+    fn overlay_draw_hook(&self, drawing: &DrawingSettings) {
+        if !self.open {
+            return;
+        }
+
+        let (width, height) = (screen_width(), screen_height());
+        let row_height = get_status_bar_row_height();
+        let font_size = row_height * 0.9;
+
+        let mut background = drawing.colour(AppColour::StatusBar);
+        background.a = 0.95;
+        draw_rectangle(0.0, 0.0, width, height, background);
+
+        let _ = draw_text_in_bounds(
+            drawing,
+            &format!("> {}", self.query),
+            20.0,
+            10.0,
+            font_size,
+            drawing.colour(AppColour::StatusBarBufferEdit),
+            (None, Some(row_height)),
+        );
+
+        if self.matches.is_empty() {
+            let _ = draw_text_in_bounds(
+                drawing,
+                "No matches",
+                20.0,
+                10.0 + row_height,
+                font_size,
+                drawing.colour(AppColour::StatusBarItemError),
+                (None, Some(row_height)),
+            );
+            return;
+        }
+
+        // The list scrolls to keep the selected entry visible
+        let visible = (((height - 20.0) / row_height) as usize)
+            .saturating_sub(1)
+            .max(1);
+        let first = (self.selected + 1).saturating_sub(visible);
+        for (row, idx) in self.matches.iter().enumerate().skip(first).take(visible) {
+            let entry = &self.entries[*idx];
+            let y = 10.0 + (row - first + 1) as f32 * row_height;
+            let colour = if row == self.selected {
+                draw_rectangle(
+                    0.0,
+                    y,
+                    width,
+                    row_height,
+                    drawing.colour(AppColour::StatusBarSeparator),
+                );
+                AppColour::StatusBarItemSelected
+            } else {
+                AppColour::StatusBarItem
+            };
+
+            let (command_width, _) = draw_text_in_bounds(
+                drawing,
+                &entry.command,
+                20.0,
+                y,
+                font_size,
+                drawing.colour(colour),
+                (None, Some(row_height)),
+            );
+            let _ = draw_text_in_bounds(
+                drawing,
+                &entry.description,
+                40.0 + command_width,
+                y,
+                font_size * 0.8,
+                drawing.colour(AppColour::StatusBarItemInProgress),
+                (None, Some(row_height)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches_rank_word_starts() {
+        assert!(fuzzy_score("bg", "BoardGen").is_some());
+        assert!(fuzzy_score("board 30", "BoardGen 30").is_some());
+        assert_eq!(fuzzy_score("gb", "BoardGen"), None);
+        assert_eq!(fuzzy_score("", "Fps"), Some(-3));
+
+        let score = |x: &str| fuzzy_score("bg", x).unwrap();
+        assert!(score("BoardGen") > score("BackgroundImage"));
+        assert!(score("bg") > score("BoardGen"));
+    }
+}
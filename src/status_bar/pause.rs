@@ -0,0 +1,68 @@
+use tracing::{debug, span, Level};
+
+use crate::{draw_helper::AppColour, game_clock};
+
+use super::{Item, StatusBar};
+
+#[derive(Default)]
+pub struct Pause;
+
+impl Item for Pause {
+    fn name(&self) -> String {
+        "Pause".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("Pause (auto=[on | off])?".to_string())
+    }
+
+    fn activated(
+        &mut self,
+        _game: &mut crate::sudoku_game::SudokuGame,
+        status_bar: &mut StatusBar,
+    ) {
+        let span = span!(Level::INFO, "PauseActivated");
+        let _enter = span.enter();
+
+        match status_bar.buffer.trim() {
+            "" => {
+                if game_clock::is_paused() {
+                    game_clock::resume();
+                } else {
+                    game_clock::pause();
+                }
+            }
+            "auto=on" | "auto=off" => {
+                let enabled = status_bar.buffer.trim() == "auto=on";
+                debug!("Setting auto pause to {enabled}");
+                game_clock::set_auto_pause(enabled);
+            }
+            _ => status_bar.buffer = "Pause: expected auto=on or auto=off".to_string(),
+        }
+    }
+
+    fn completions(&self, _status_bar: &StatusBar, args: &[&str]) -> Vec<String> {
+        match args {
+            [] => vec!["auto=on".to_string(), "auto=off".to_string()],
+            _ => Vec::new(),
+        }
+    }
+
+    fn update(
+        &mut self,
+        _game: &mut crate::sudoku_game::SudokuGame,
+        status_bar: &mut StatusBar,
+    ) -> (String, macroquad::prelude::Color) {
+        let colour = if game_clock::is_paused() {
+            AppColour::StatusBarItemInProgress
+        } else {
+            AppColour::StatusBarItem
+        };
+
+        (String::new(), status_bar.drawing.colour(colour))
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::NameOnly
+    }
+}
@@ -20,6 +20,10 @@ impl Item for PencilMarks {
         "PencilMarks".to_string()
     }
 
+    fn help(&self) -> Option<String> {
+        Some("PencilMarks [number]".to_string())
+    }
+
     fn activated(
         &mut self,
         _game: &mut crate::sudoku_game::SudokuGame,
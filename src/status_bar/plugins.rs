@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use tracing::{debug, error, info, span, trace, warn, Level};
+
+use crate::{config, draw_helper::AppColour, sudoku_game::SudokuGame};
+
+use super::{eval, Item, ItemOkData, ItemStatus, StatusBar};
+
+const PLUGINS_DIR: &str = "plugins";
+const MANIFEST_FILE: &str = "manifest.txt";
+const STATE_FILE: &str = "plugins/state.txt";
+
+lazy_static! {
+    /// The manifests last found by `discover`, so that `require` doesn't read every manifest again
+    static ref DISCOVERED: Mutex<Option<Vec<Manifest>>> = Mutex::new(None);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    pub dir: String,
+    pub name: String,
+    pub version: String,
+    pub entry: String,
+    pub dependencies: Vec<String>,
+    pub enabled: bool,
+}
+
+impl Manifest {
+    /// Manifests are made up of `key = value` lines, where only `name` is required. Lines starting
+    /// with `#` are ignored
+    fn parse(dir: &str, contents: &str) -> Result<Self, String> {
+        let mut manifest = Self {
+            dir: dir.to_string(),
+            name: String::new(),
+            version: "0.0.0".to_string(),
+            entry: "init.lua".to_string(),
+            dependencies: vec![],
+            enabled: true,
+        };
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("Invalid line: '{line}'"));
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                "name" => manifest.name = value.to_string(),
+                "version" => manifest.version = value.to_string(),
+                "entry" => manifest.entry = value.to_string(),
+                "dependencies" => {
+                    manifest.dependencies = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|x| !x.is_empty())
+                        .map(ToString::to_string)
+                        .collect();
+                }
+                "enabled" => {
+                    manifest.enabled = value
+                        .parse()
+                        .map_err(|_| format!("Invalid value for enabled: '{value}'"))?;
+                }
+                x => return Err(format!("Unknown key: '{x}'")),
+            }
+        }
+
+        if manifest.name.is_empty() || manifest.name.contains(char::is_whitespace) {
+            return Err("Missing or invalid name".to_string());
+        }
+        if config::get_sandboxed_file_path(&manifest.entry).is_none() {
+            return Err(format!(
+                "Entry '{}' must be a relative path within the plugin directory",
+                manifest.entry
+            ));
+        }
+
+        Ok(manifest)
+    }
+
+    fn entry_path(&self) -> String {
+        format!("{PLUGINS_DIR}/{}/{}", self.dir, self.entry)
+    }
+}
+
+/// Reads the manifest of every directory in the plugins directory, applying the enabled state saved
+/// by the `Plugins` item over the manifest defaults
+pub fn discover() -> Vec<Manifest> {
+    let span = span!(Level::INFO, "DiscoverPlugins");
+    let _enter = span.enter();
+
+    let Ok(dirs) = std::fs::read_dir(config::get_file_path(PLUGINS_DIR)) else {
+        debug!("No plugins directory");
+        return vec![];
+    };
+
+    let state = load_state();
+    let mut manifests = dirs
+        .filter_map(Result::ok)
+        .filter(|x| x.path().is_dir())
+        .filter_map(|x| {
+            let dir = x.file_name().to_string_lossy().to_string();
+            let contents = std::fs::read_to_string(x.path().join(MANIFEST_FILE)).ok()?;
+            match Manifest::parse(&dir, &contents) {
+                Ok(mut manifest) => {
+                    if let Some(enabled) = state.get(&manifest.name) {
+                        manifest.enabled = *enabled;
+                    }
+                    Some(manifest)
+                }
+                Err(e) => {
+                    error!("Invalid manifest for plugin '{dir}': {e}");
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    manifests.sort_by(|a, b| a.name.cmp(&b.name));
+    trace!("Found {} plugin/s", manifests.len());
+    *DISCOVERED.lock().unwrap() = Some(manifests.clone());
+    manifests
+}
+
+fn load_state() -> HashMap<String, bool> {
+    let Ok(contents) = std::fs::read_to_string(config::get_file_path(STATE_FILE)) else {
+        return HashMap::new();
+    };
+
+    contents
+        .lines()
+        .filter_map(|x| {
+            let (name, state) = x.split_once(' ')?;
+            Some((name.to_string(), state == "enabled"))
+        })
+        .collect()
+}
+
+fn save_state(manifests: &[Manifest]) {
+    let contents = manifests
+        .iter()
+        .map(|x| {
+            let state = if x.enabled { "enabled" } else { "disabled" };
+            format!("{} {state}\n", x.name)
+        })
+        .collect::<String>();
+
+    if let Err(e) = std::fs::write(config::get_file_path(STATE_FILE), contents) {
+        error!("Unable to save plugin state: {e}");
+    }
+}
+
+/// Orders the plugins in `wanted` (and their dependencies) so that each plugin comes after the
+/// plugins it depends on
+fn load_order<'a>(manifests: &'a [Manifest], wanted: &[&str]) -> Result<Vec<&'a Manifest>, String> {
+    fn visit<'a>(
+        manifests: &'a [Manifest],
+        name: &str,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<&'a Manifest>,
+    ) -> Result<(), String> {
+        if order.iter().any(|x| x.name == name) {
+            return Ok(());
+        }
+        if visiting.iter().any(|x| x == name) {
+            return Err(format!("Circular dependency on '{name}'"));
+        }
+
+        let Some(manifest) = manifests.iter().find(|x| x.name == name) else {
+            return Err(format!("Missing plugin '{name}'"));
+        };
+
+        visiting.push(name.to_string());
+        for dependency in &manifest.dependencies {
+            visit(manifests, dependency, visiting, order)?;
+        }
+        visiting.pop();
+
+        order.push(manifest);
+        Ok(())
+    }
+
+    let mut order = vec![];
+    for name in wanted {
+        visit(manifests, name, &mut vec![], &mut order)?;
+    }
+    Ok(order)
+}
+
+/// Finds the file for a module passed to `require`, where `a.b` refers to `a/b.lua` inside either
+/// the `lib` directory, or any plugin directory
+pub fn find_module(module: &str) -> Option<PathBuf> {
+    let segments = module.split('.').collect::<Vec<_>>();
+    if segments.iter().any(|x| {
+        x.is_empty()
+            || !x
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+    }) {
+        return None;
+    }
+
+    let file = format!("{}.lua", segments.join("/"));
+    let manifests = DISCOVERED.lock().unwrap().clone().unwrap_or_else(discover);
+    std::iter::once(format!("lib/{file}"))
+        .chain(
+            manifests
+                .into_iter()
+                .map(|x| format!("{PLUGINS_DIR}/{}/{file}", x.dir)),
+        )
+        .map(|x| config::get_file_path(&x))
+        .find(|x| x.is_file())
+}
+
+pub struct Plugins {
+    manifests: Vec<Manifest>,
+    loaded: Vec<String>,
+    failed: Vec<String>,
+    started: bool,
+}
+
+impl Default for Plugins {
+    fn default() -> Self {
+        Self {
+            manifests: discover(),
+            loaded: vec![],
+            failed: vec![],
+            started: false,
+        }
+    }
+}
+
+impl Plugins {
+    fn load(&mut self, names: &[&str], game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let order = match load_order(&self.manifests, names) {
+            Ok(order) => order,
+            Err(e) => {
+                error!("Unable to load plugins: {e}");
+                status_bar.buffer = format!("Plugins: {e}");
+                return;
+            }
+        };
+
+        for manifest in order {
+            if self.loaded.contains(&manifest.name) {
+                continue;
+            }
+
+            info!(
+                "Loading plugin '{}' v{}...",
+                manifest.name, manifest.version
+            );
+            self.failed.retain(|x| *x != manifest.name);
+            if let Err(e) = eval::load_script_file(&manifest.entry_path(), game, status_bar) {
+                error!("Unable to load plugin '{}': {e}", manifest.name);
+                status_bar.buffer = format!("Plugins: {} failed ({e})", manifest.name);
+                self.failed.push(manifest.name.clone());
+                return;
+            }
+            self.loaded.push(manifest.name.clone());
+        }
+    }
+
+    fn unload(&mut self, name: &str, status_bar: &mut StatusBar) {
+        let Some(manifest) = self.manifests.iter().find(|x| x.name == name) else {
+            return;
+        };
+        let entry_path = manifest.entry_path();
+
+        let script_name = status_bar.items.iter_mut().find_map(|x| match x.status() {
+            ItemStatus::Ok(ItemOkData::LuaScript(script)) if script == entry_path => Some(x.name()),
+            _ => None,
+        });
+
+        if let Some(script_name) = script_name {
+            if let Err(e) = status_bar.remove_item(&script_name) {
+                warn!("Unable to remove plugin script '{script_name}': {e}");
+            }
+        }
+        self.loaded.retain(|x| x != name);
+    }
+
+    fn set_enabled(
+        &mut self,
+        name: &str,
+        enabled: bool,
+        game: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+    ) {
+        if !self.manifests.iter().any(|x| x.name == name) {
+            status_bar.buffer = format!("Plugins: could not find '{name}'");
+            return;
+        }
+
+        if !enabled {
+            if let Some(dependent) = self
+                .manifests
+                .iter()
+                .find(|x| x.enabled && x.dependencies.iter().any(|y| y == name))
+            {
+                status_bar.buffer = format!("Plugins: '{name}' is needed by '{}'", dependent.name);
+                return;
+            }
+        }
+
+        let names = load_order(&self.manifests, &[name])
+            .map(|x| x.iter().map(|y| y.name.clone()).collect::<Vec<_>>())
+            .unwrap_or_else(|_| vec![name.to_string()]);
+        for manifest in &mut self.manifests {
+            // Enabling a plugin also enables everything it depends on
+            if manifest.name == name || (enabled && names.contains(&manifest.name)) {
+                manifest.enabled = enabled;
+            }
+        }
+        save_state(&self.manifests);
+
+        if enabled {
+            self.load(&[name], game, status_bar);
+        } else {
+            self.unload(name, status_bar);
+        }
+    }
+
+    fn list(&self) -> String {
+        if self.manifests.is_empty() {
+            return "No plugins found".to_string();
+        }
+
+        self.manifests
+            .iter()
+            .map(|x| {
+                let state = if self.failed.contains(&x.name) {
+                    "failed"
+                } else if self.loaded.contains(&x.name) {
+                    "loaded"
+                } else if x.enabled {
+                    "enabled"
+                } else {
+                    "disabled"
+                };
+                format!("{} v{} ({state})", x.name, x.version)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Item for Plugins {
+    fn name(&self) -> String {
+        "Plugins".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("Plugins [list | enable <name> | disable <name> | refresh]".to_string())
+    }
+
+    fn activated(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "PluginsActivated");
+        let _enter = span.enter();
+
+        let buffer = status_bar.buffer.clone();
+        let mut args = buffer.split_whitespace();
+        match (args.next(), args.next()) {
+            (None | Some("list"), None) => status_bar.buffer = self.list(),
+            (Some("enable"), Some(name)) => self.set_enabled(name, true, game, status_bar),
+            (Some("disable"), Some(name)) => self.set_enabled(name, false, game, status_bar),
+            (Some("refresh"), None) => {
+                self.manifests = discover();
+                status_bar.buffer = self.list();
+            }
+            _ => status_bar.buffer = "Plugins: unknown command".to_string(),
+        }
+    }
+
+    fn update(
+        &mut self,
+        game: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+    ) -> (String, macroquad::prelude::Color) {
+        if !self.started {
+            self.started = true;
+            let enabled = self
+                .manifests
+                .iter()
+                .filter(|x| x.enabled)
+                .map(|x| x.name.clone())
+                .collect::<Vec<_>>();
+            self.load(
+                &enabled.iter().map(String::as_str).collect::<Vec<_>>(),
+                game,
+                status_bar,
+            );
+        }
+
+        let colour = if self.failed.is_empty() {
+            AppColour::StatusBarItemOkay
+        } else {
+            AppColour::StatusBarItemError
+        };
+
+        (
+            format!("{}/{}", self.loaded.len(), self.manifests.len()),
+            status_bar.drawing.colour(colour),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn manifest(name: &str, dependencies: &[&str]) -> Manifest {
+        Manifest {
+            dir: name.to_string(),
+            name: name.to_string(),
+            version: "1.0".to_string(),
+            entry: "init.lua".to_string(),
+            dependencies: dependencies.iter().map(ToString::to_string).collect(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn parse_manifest() {
+        let parsed = Manifest::parse(
+            "dir",
+            "# comment\nname = timer\nversion = 1.2\nentry = main.lua\ndependencies = util, colours\nenabled = false\n",
+        );
+        assert_eq!(
+            parsed,
+            Ok(Manifest {
+                dir: "dir".to_string(),
+                name: "timer".to_string(),
+                version: "1.2".to_string(),
+                entry: "main.lua".to_string(),
+                dependencies: vec!["util".to_string(), "colours".to_string()],
+                enabled: false,
+            })
+        );
+
+        assert!(Manifest::parse("dir", "version = 1.0").is_err());
+        assert!(Manifest::parse("dir", "name = a\nfoo = bar").is_err());
+        assert!(Manifest::parse("dir", "name = a\nenabled = maybe").is_err());
+        assert!(Manifest::parse("dir", "name = a\nentry = ../../other.lua").is_err());
+        assert!(Manifest::parse("dir", "name = a\nentry = /tmp/other.lua").is_err());
+    }
+
+    #[test]
+    fn dependency_order() {
+        let manifests = [
+            manifest("a", &["b", "c"]),
+            manifest("b", &["c"]),
+            manifest("c", &[]),
+        ];
+        let order = load_order(&manifests, &["a"])
+            .unwrap()
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["c", "b", "a"]);
+
+        let missing = [manifest("a", &["z"])];
+        assert!(load_order(&missing, &["a"]).is_err());
+
+        let circular = [manifest("a", &["b"]), manifest("b", &["a"])];
+        assert!(load_order(&circular, &["a"]).is_err());
+    }
+}
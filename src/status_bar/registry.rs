@@ -0,0 +1,193 @@
+use super::{
+    add::Add, alias::Alias, background_image::BackgroundImage, bar_layout::BarLayout,
+    board_gen::BoardGen, colour_overwrite::ColourOverwrite, cpu_solve::SolveTask, eval::Eval,
+    find::Find, font::Font, fps::Fps, hard_reset::HardReset, help::Help, list::BuiltinList,
+    on_board_init::OnBoardInit, padding::Padding, palette::Palette, pause::Pause,
+    pencil_marks::PencilMarks, plugins::Plugins, remove::BuiltinRemove, reorder::BuiltinMove,
+    replay::Replay, scripts::Scripts, set::Set, stats::Stats, Item,
+};
+
+/// How an item is created when it's added to the status bar
+#[derive(Clone)]
+pub enum ItemSource {
+    Builtin(fn() -> Box<dyn Item>),
+    /// A command which adds the item, such as `Eval @!timer.lua` for items registered by scripts
+    Command(String),
+}
+
+#[derive(Clone)]
+pub struct ItemFactory {
+    pub name: String,
+    pub description: String,
+    pub usage: String,
+    /// Whether adding the item is refused if an item with the same name is already added
+    pub singleton: bool,
+    pub source: ItemSource,
+}
+
+impl ItemFactory {
+    /// A factory for the built-in item `T`, whose usage is the one given by its `help`
+    fn builtin<T: Item + Default + 'static>(name: &str, description: &str) -> Self {
+        fn create<T: Item + Default + 'static>() -> Box<dyn Item> {
+            Box::<T>::default()
+        }
+
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            usage: T::default().help().unwrap_or_else(|| name.to_string()),
+            singleton: false,
+            source: ItemSource::Builtin(create::<T>),
+        }
+    }
+
+    fn singleton(mut self) -> Self {
+        self.singleton = true;
+        self
+    }
+}
+
+/// Every item which can be added with `BuiltinAdd`
+pub struct Registry {
+    factories: Vec<ItemFactory>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            factories: vec![
+                ItemFactory::builtin::<Add>("BuiltinAdd", "Adds items to the status bar")
+                    .singleton(),
+                ItemFactory::builtin::<BuiltinRemove>(
+                    "BuiltinRemove",
+                    "Removes items from the status bar",
+                )
+                .singleton(),
+                ItemFactory::builtin::<BuiltinList>(
+                    "BuiltinList",
+                    "Lists added and available items",
+                )
+                .singleton(),
+                ItemFactory::builtin::<BuiltinMove>(
+                    "BuiltinMove",
+                    "Moves an item to another position on the status bar",
+                )
+                .singleton(),
+                ItemFactory::builtin::<Set>("Set", "Sets variables for commands").singleton(),
+                ItemFactory::builtin::<Help>("Help", "Shows the usage of items").singleton(),
+                ItemFactory::builtin::<Palette>(
+                    "Palette",
+                    "Searches and runs commands, items, scripts, aliases and recent commands",
+                )
+                .singleton(),
+                ItemFactory::builtin::<BoardGen>(
+                    "BoardGen",
+                    "Generates a board, or sets the board from a string",
+                ),
+                ItemFactory::builtin::<OnBoardInit>(
+                    "OnBoardInit",
+                    "Runs a command whenever the board is reset",
+                ),
+                ItemFactory::builtin::<SolveTask>("CpuSolve", "Solves the board"),
+                ItemFactory::builtin::<Fps>("Fps", "Shows and limits the FPS").singleton(),
+                ItemFactory::builtin::<ColourOverwrite>(
+                    "ColourOverwrite",
+                    "Overwrites a colour of the app",
+                ),
+                ItemFactory::builtin::<BackgroundImage>(
+                    "BackgroundImage",
+                    "Draws an image behind the board",
+                ),
+                ItemFactory::builtin::<PencilMarks>(
+                    "PencilMarks",
+                    "Shows the possible numbers of cells",
+                ),
+                ItemFactory::builtin::<Padding>("Padding", "Animates the padding around the board"),
+                ItemFactory::builtin::<HardReset>("HardReset", "Reloads the config").singleton(),
+                ItemFactory::builtin::<Find>("Find", "Highlights cells with a number"),
+                ItemFactory::builtin::<Font>("Font", "Sets the font"),
+                ItemFactory::builtin::<Eval>("Eval", "Evaluates Lua code and loads Lua scripts"),
+                ItemFactory::builtin::<Stats>("Stats", "Shows solve statistics").singleton(),
+                ItemFactory::builtin::<Pause>("Pause", "Pauses the game").singleton(),
+                ItemFactory::builtin::<Replay>("Replay", "Records and plays back solves")
+                    .singleton(),
+                ItemFactory::builtin::<Plugins>(
+                    "Plugins",
+                    "Loads plugins from the plugins directory",
+                )
+                .singleton(),
+                ItemFactory::builtin::<Scripts>("Scripts", "Shows the health of Lua scripts"),
+                ItemFactory::builtin::<BarLayout>(
+                    "StatusBarLayout",
+                    "Places the status bar, and sets how it handles overflowing items",
+                ),
+                ItemFactory::builtin::<Alias>("Alias", "Adds user-defined shorthands"),
+            ],
+        }
+    }
+}
+
+impl Registry {
+    pub fn get(&self, name: &str) -> Option<&ItemFactory> {
+        self.factories
+            .iter()
+            .find(|x| x.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ItemFactory> {
+        self.factories.iter()
+    }
+
+    /// Adds `factory` to the registry, replacing a previously registered item of the same name,
+    /// unless that item is built-in
+    pub fn register(&mut self, factory: ItemFactory) -> Result<(), String> {
+        match self
+            .factories
+            .iter_mut()
+            .find(|x| x.name.eq_ignore_ascii_case(&factory.name))
+        {
+            Some(ItemFactory {
+                source: ItemSource::Builtin(_),
+                ..
+            }) => Err(format!("Cannot replace built-in item '{}'", factory.name)),
+            Some(existing) => {
+                *existing = factory;
+                Ok(())
+            }
+            None => {
+                self.factories.push(factory);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn registered_items_replace_only_scripts() {
+        let mut registry = Registry::default();
+        assert!(registry.get("fps").is_some_and(|x| x.singleton));
+        assert!(registry.get("find").is_some_and(|x| !x.singleton));
+        assert!(registry.get("palette").is_some_and(|x| x.singleton));
+        assert_eq!(registry.get("set").unwrap().usage, "Set ([name] [value]?)?");
+
+        let mut factory = ItemFactory {
+            name: "Timer".to_string(),
+            description: "Times things".to_string(),
+            usage: "Timer".to_string(),
+            singleton: false,
+            source: ItemSource::Command("Eval @!timer.lua".to_string()),
+        };
+        assert!(registry.register(factory.clone()).is_ok());
+
+        factory.usage = "Timer [seconds]".to_string();
+        assert!(registry.register(factory.clone()).is_ok());
+        assert_eq!(registry.get("timer").unwrap().usage, "Timer [seconds]");
+
+        factory.name = "FPS".to_string();
+        assert!(registry.register(factory).is_err());
+    }
+}
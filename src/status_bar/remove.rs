@@ -0,0 +1,44 @@
+use tracing::{span, trace, Level};
+
+use crate::sudoku_game::SudokuGame;
+
+use super::{Item, StatusBar};
+
+#[derive(Default)]
+pub struct BuiltinRemove;
+
+impl Item for BuiltinRemove {
+    fn name(&self) -> String {
+        "BuiltinRemove".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("BuiltinRemove [item_name]...".to_string())
+    }
+
+    fn activated(&mut self, _game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "BuiltinRemoveActivate");
+        let _enter = span.enter();
+
+        let buffer = status_bar.buffer.clone();
+        for item in buffer.split_whitespace() {
+            trace!("Removing item: '{}'...", item);
+            if let Err(e) = status_bar.remove_item(item) {
+                status_bar.buffer = format!("BuiltinRemove: {e}");
+                break;
+            }
+        }
+    }
+
+    fn completions(&self, status_bar: &StatusBar, _args: &[&str]) -> Vec<String> {
+        status_bar
+            .items()
+            .map(|x| x.name())
+            .filter(|x| !status_bar.is_core_item(x))
+            .collect()
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::None
+    }
+}
@@ -0,0 +1,50 @@
+use tracing::{span, Level};
+
+use crate::sudoku_game::SudokuGame;
+
+use super::{Item, StatusBar};
+
+#[derive(Default)]
+pub struct BuiltinMove;
+
+impl Item for BuiltinMove {
+    fn name(&self) -> String {
+        "BuiltinMove".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("BuiltinMove [item_name] [position]".to_string())
+    }
+
+    fn activated(&mut self, _game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "BuiltinMoveActivate");
+        let _enter = span.enter();
+
+        let buffer = status_bar.buffer.clone();
+        let mut args = buffer.split_whitespace();
+        let (Some(name), Some(Ok(position)), None) = (
+            args.next(),
+            args.next().map(str::parse::<usize>),
+            args.next(),
+        ) else {
+            status_bar.buffer = "BuiltinMove: expected an item and a position".to_string();
+            return;
+        };
+
+        if let Err(e) = status_bar.move_item(name, position.saturating_sub(1)) {
+            status_bar.buffer = format!("BuiltinMove: {e}");
+        }
+    }
+
+    fn completions(&self, status_bar: &StatusBar, args: &[&str]) -> Vec<String> {
+        if !args.is_empty() {
+            return Vec::new();
+        }
+
+        status_bar.items().map(|x| x.name()).collect()
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::None
+    }
+}
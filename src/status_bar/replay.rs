@@ -0,0 +1,500 @@
+use macroquad::{miniquad::KeyCode, time::get_frame_time};
+use ndarray::Array2;
+use tracing::{debug, error, span, trace, Level};
+
+use crate::{
+    config,
+    draw_helper::AppColour,
+    game_clock,
+    input_helper::{InputAction, InputActionContext},
+    status_bar::stats::format_duration_ms,
+    sudoku_game::SudokuGame,
+};
+
+use super::{HookAction, Item, ItemOkData, ItemStatus, StatusBar};
+
+const REPLAY_EXTENSION: &str = ".replay";
+
+#[derive(Debug, PartialEq, Eq)]
+enum ReplayEvent {
+    Input(String),
+    Cell { idx: usize, value: u8 },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct TimedEvent {
+    ms: u64,
+    event: ReplayEvent,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Recording {
+    board: String,
+    events: Vec<TimedEvent>,
+}
+
+impl Recording {
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let board = lines.next()?.strip_prefix("board ")?.trim().to_string();
+        SudokuGame::generate_cells_from_string(&board)?;
+
+        let mut events = vec![];
+        for line in lines.filter(|x| !x.trim().is_empty()) {
+            let mut args = line.splitn(3, ' ');
+            let ms = args.next()?.parse::<u64>().ok()?;
+            let event = match (args.next()?, args.next()?) {
+                ("input", action) => ReplayEvent::Input(action.to_string()),
+                ("cell", cell) => {
+                    let (idx, value) = cell.split_once(' ')?;
+                    ReplayEvent::Cell {
+                        idx: idx.parse().ok()?,
+                        value: value.parse().ok()?,
+                    }
+                }
+                _ => return None,
+            };
+            events.push(TimedEvent { ms, event });
+        }
+
+        Some(Self { board, events })
+    }
+
+    fn serialise(&self) -> String {
+        let mut buf = format!("board {}\n", self.board);
+        for TimedEvent { ms, event } in &self.events {
+            match event {
+                ReplayEvent::Input(action) => buf.push_str(&format!("{ms} input {action}\n")),
+                ReplayEvent::Cell { idx, value } => {
+                    buf.push_str(&format!("{ms} cell {idx} {value}\n"));
+                }
+            }
+        }
+        buf
+    }
+
+    fn duration_ms(&self) -> u64 {
+        self.events.last().map_or(0, |x| x.ms)
+    }
+
+    fn cells_at(&self, ms: u64) -> Option<Array2<u8>> {
+        let mut cells = SudokuGame::generate_cells_from_string(&self.board)?;
+        for TimedEvent { event, .. } in self.events.iter().take_while(|x| x.ms <= ms) {
+            if let ReplayEvent::Cell { idx, value } = event {
+                *cells.iter_mut().nth(*idx)? = *value;
+            }
+        }
+        Some(cells)
+    }
+
+    fn last_input_at(&self, ms: u64) -> Option<&str> {
+        self.events
+            .iter()
+            .take_while(|x| x.ms <= ms)
+            .filter_map(|x| match &x.event {
+                ReplayEvent::Input(action) => Some(action.as_str()),
+                ReplayEvent::Cell { .. } => None,
+            })
+            .last()
+    }
+
+    fn next_event_after(&self, ms: u64) -> Option<u64> {
+        self.events.iter().map(|x| x.ms).find(|x| *x > ms)
+    }
+
+    fn previous_event_before(&self, ms: u64) -> Option<u64> {
+        self.events.iter().map(|x| x.ms).rfind(|x| *x < ms)
+    }
+}
+
+enum ReplayMode {
+    Recording {
+        start_ms: Option<u128>,
+        last_cells: Option<Vec<u8>>,
+    },
+    Playback {
+        cursor_ms: f64,
+        speed: f64,
+        playing: bool,
+    },
+}
+
+pub struct Replay {
+    recording: Recording,
+    mode: ReplayMode,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self {
+            recording: Recording::default(),
+            mode: ReplayMode::Recording {
+                start_ms: None,
+                last_cells: None,
+            },
+        }
+    }
+}
+
+impl Replay {
+    fn record(&mut self, game: &SudokuGame) {
+        let ReplayMode::Recording {
+            start_ms,
+            last_cells,
+        } = &mut self.mode
+        else {
+            return;
+        };
+
+        let now = game_clock::game_time_ms();
+        let Some(start) = *start_ms else {
+            trace!("Starting new recording...");
+            *start_ms = Some(now);
+            *last_cells = Some(game.cells.iter().copied().collect());
+
+            // The board's givens are recorded, followed by any tiles already placed
+            let board = game.givens_string();
+            let events = board
+                .chars()
+                .zip(game.cells.iter())
+                .enumerate()
+                .filter(|(_, (given, value))| **value != 0 && given.to_digit(10) == Some(0))
+                .map(|(idx, (_, value))| TimedEvent {
+                    ms: 0,
+                    event: ReplayEvent::Cell { idx, value: *value },
+                })
+                .collect();
+            self.recording = Recording { board, events };
+            return;
+        };
+        let ms = now.saturating_sub(start) as u64;
+
+        if let Some(action) = InputAction::get_last_input(InputActionContext::Generic, &game.input)
+        {
+            self.recording.events.push(TimedEvent {
+                ms,
+                event: ReplayEvent::Input(format!("{action:?}")),
+            });
+        }
+
+        let cells = game.cells.iter().copied().collect::<Vec<_>>();
+        if let Some(last_cells) = last_cells.replace(cells.clone()) {
+            for (idx, (now, before)) in cells.iter().zip(last_cells.iter()).enumerate() {
+                if now != before {
+                    self.recording.events.push(TimedEvent {
+                        ms,
+                        event: ReplayEvent::Cell { idx, value: *now },
+                    });
+                }
+            }
+        }
+    }
+
+    fn play(&mut self, game: &mut SudokuGame) {
+        let ReplayMode::Playback {
+            cursor_ms,
+            speed,
+            playing,
+        } = &mut self.mode
+        else {
+            return;
+        };
+
+        if *playing {
+            *cursor_ms += f64::from(get_frame_time()) * 1000.0 * *speed;
+        }
+
+        let duration = self.recording.duration_ms() as f64;
+        if *cursor_ms >= duration {
+            *cursor_ms = duration;
+            *playing = false;
+        }
+
+        if let Some(cells) = self.recording.cells_at(*cursor_ms as u64) {
+            game.cells = cells;
+        }
+    }
+
+    fn save(&self, name: &str) -> Result<String, String> {
+        let name = if name.is_empty() {
+            let time = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|x| x.as_secs())
+                .unwrap_or(0);
+            format!("replay-{time}{REPLAY_EXTENSION}")
+        } else if name.ends_with(REPLAY_EXTENSION) {
+            name.to_string()
+        } else {
+            format!("{name}{REPLAY_EXTENSION}")
+        };
+
+        std::fs::write(Self::path(&name)?, self.recording.serialise())
+            .map(|()| name)
+            .map_err(|e| e.to_string())
+    }
+
+    /// The path of the replay `name`, which has to be within the config directory since scripts
+    /// can save and load replays with `commands.run`
+    fn path(name: &str) -> Result<std::path::PathBuf, String> {
+        config::get_sandboxed_file_path(name).ok_or_else(|| {
+            format!("Replay '{name}' must be a relative path within the config directory")
+        })
+    }
+
+    fn load(&mut self, name: &str, game: &mut SudokuGame) -> Result<(), String> {
+        let path = if name.ends_with(REPLAY_EXTENSION) {
+            Self::path(name)?
+        } else {
+            Self::path(&format!("{name}{REPLAY_EXTENSION}"))?
+        };
+
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let recording = Recording::parse(&text).ok_or("InvalidReplay".to_string())?;
+
+        game.reset(SudokuGame::new(Some(&recording.board)));
+        self.recording = recording;
+        self.mode = ReplayMode::Playback {
+            cursor_ms: 0.0,
+            speed: 1.0,
+            playing: true,
+        };
+
+        Ok(())
+    }
+
+    fn playback_command(&mut self, command: &str, arg: Option<&str>) -> Result<(), String> {
+        let ReplayMode::Playback {
+            cursor_ms,
+            speed,
+            playing,
+        } = &mut self.mode
+        else {
+            return Err("Replay: not playing back".to_string());
+        };
+
+        match (command, arg) {
+            ("play", None) => {
+                if *cursor_ms >= self.recording.duration_ms() as f64 {
+                    *cursor_ms = 0.0;
+                }
+                *playing = !*playing;
+            }
+            ("speed", Some(value)) => {
+                *speed = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|x| *x > 0.0)
+                    .ok_or("Replay: invalid speed".to_string())?;
+            }
+            ("seek", Some(value)) => {
+                let secs = value
+                    .parse::<f64>()
+                    .map_err(|_| "Replay: invalid seek time".to_string())?;
+                *cursor_ms = (secs * 1000.0).clamp(0.0, self.recording.duration_ms() as f64);
+            }
+            ("step", direction) => {
+                *playing = false;
+                let cursor = *cursor_ms as u64;
+                let target = if direction == Some("-1") {
+                    self.recording.previous_event_before(cursor)
+                } else {
+                    self.recording.next_event_after(cursor)
+                };
+                if let Some(target) = target {
+                    *cursor_ms = target as f64;
+                }
+            }
+            _ => return Err("Replay: unknown command".to_string()),
+        }
+
+        Ok(())
+    }
+}
+
+impl Item for Replay {
+    fn name(&self) -> String {
+        "Replay".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("Replay (save [name]?)|(load [name])|(play)|(speed [multiplier])|(seek [seconds])|(step (-1)?)|(stop)".to_string())
+    }
+
+    fn activated(&mut self, game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "ReplayActivated");
+        let _enter = span.enter();
+
+        let buffer = status_bar.buffer.clone();
+        let mut args = buffer.split_whitespace();
+        let command = args.next().unwrap_or(match self.mode {
+            ReplayMode::Recording { .. } => "save",
+            ReplayMode::Playback { .. } => "play",
+        });
+        let arg = args.next();
+
+        let result = match command {
+            "save" => self.save(arg.unwrap_or_default()).map(|name| {
+                debug!("Saved replay to '{name}'");
+                status_bar.buffer = format!("Saved {name}");
+            }),
+            "load" => match arg {
+                Some(name) => self.load(name, game),
+                None => Err("Replay: expected replay name".to_string()),
+            },
+            "stop" => {
+                *self = Self::default();
+                Ok(())
+            }
+            _ => self.playback_command(command, arg),
+        };
+
+        if let Err(e) = result {
+            error!("{e}");
+            status_bar.buffer = e;
+        }
+    }
+
+    fn update(
+        &mut self,
+        game: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+    ) -> (String, macroquad::prelude::Color) {
+        let span = span!(Level::INFO, "ReplayUpdate");
+        let _enter = span.enter();
+
+        self.record(game);
+        self.play(game);
+
+        match self.mode {
+            ReplayMode::Recording { .. } => (
+                format!("rec {}", self.recording.events.len()),
+                status_bar.drawing.colour(AppColour::StatusBarItem),
+            ),
+            ReplayMode::Playback {
+                cursor_ms,
+                speed,
+                playing,
+            } => {
+                let mut text = format!(
+                    "{}/{} x{speed}",
+                    format_duration_ms(cursor_ms as u64),
+                    format_duration_ms(self.recording.duration_ms())
+                );
+                if let Some(action) = self.recording.last_input_at(cursor_ms as u64) {
+                    text.push_str(&format!(" {action}"));
+                }
+                let colour = if playing {
+                    AppColour::StatusBarItemInProgress
+                } else {
+                    AppColour::StatusBarItemOkay
+                };
+                (text, status_bar.drawing.colour(colour))
+            }
+        }
+    }
+
+    fn board_init(&mut self, _game: &mut SudokuGame, _status_bar: &mut StatusBar) {
+        if let ReplayMode::Recording { .. } = self.mode {
+            *self = Self::default();
+        }
+    }
+
+    fn status(&mut self) -> ItemStatus<'_> {
+        match self.mode {
+            ReplayMode::Recording { .. } => ItemStatus::Ok(ItemOkData::None),
+            ReplayMode::Playback { .. } => ItemStatus::Ok(ItemOkData::ReplayPlayback),
+        }
+    }
+
+    /// Cell input is ignored during playback, as the replay sets the cells every frame
+    fn key_hook(
+        &mut self,
+        game: &mut SudokuGame,
+        _status_bar: &mut StatusBar,
+        _key: KeyCode,
+    ) -> HookAction<()> {
+        if let ReplayMode::Recording { .. } = self.mode {
+            return HookAction::Continue(());
+        }
+
+        match InputAction::get_last_input(InputActionContext::Generic, &game.input) {
+            Some(
+                InputAction::NumberEntered(_)
+                | InputAction::Clear
+                | InputAction::AutoPlay
+                | InputAction::Reset,
+            ) => HookAction::Stop,
+            _ => HookAction::Continue(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BOARD: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn recording_round_trip() {
+        let recording = Recording {
+            board: BOARD.to_string(),
+            events: vec![
+                TimedEvent {
+                    ms: 0,
+                    event: ReplayEvent::Input("NumberEntered(4)".to_string()),
+                },
+                TimedEvent {
+                    ms: 0,
+                    event: ReplayEvent::Cell { idx: 2, value: 4 },
+                },
+                TimedEvent {
+                    ms: 1500,
+                    event: ReplayEvent::Cell { idx: 2, value: 0 },
+                },
+            ],
+        };
+        assert_eq!(
+            Some(&recording),
+            Recording::parse(&recording.serialise()).as_ref()
+        );
+    }
+
+    #[test]
+    fn recording_invalid() {
+        assert!(Recording::parse("").is_none());
+        assert!(Recording::parse("board 123").is_none());
+        assert!(Recording::parse(&format!("board {BOARD}\nabc cell 1 2")).is_none());
+        assert!(Recording::parse(&format!("board {BOARD}\n10 jump 1 2")).is_none());
+    }
+
+    #[test]
+    fn recording_starts_from_givens() {
+        let mut game = SudokuGame::new(Some(BOARD));
+        game.cells[(0, 2)] = 4;
+
+        let mut replay = Replay::default();
+        replay.record(&game);
+        assert_eq!(replay.recording.board, BOARD);
+        assert_eq!(
+            replay.recording.events,
+            vec![TimedEvent {
+                ms: 0,
+                event: ReplayEvent::Cell { idx: 2, value: 4 },
+            }]
+        );
+    }
+
+    #[test]
+    fn recording_seek() {
+        let recording =
+            Recording::parse(&format!("board {BOARD}\n100 cell 2 4\n200 cell 3 6\n")).unwrap();
+        assert_eq!(recording.cells_at(0).unwrap().iter().nth(2), Some(&0));
+        assert_eq!(recording.cells_at(150).unwrap().iter().nth(2), Some(&4));
+        assert_eq!(recording.cells_at(150).unwrap().iter().nth(3), Some(&0));
+        assert_eq!(recording.cells_at(200).unwrap().iter().nth(3), Some(&6));
+        assert_eq!(recording.next_event_after(100), Some(200));
+        assert_eq!(recording.previous_event_before(100), None);
+    }
+}
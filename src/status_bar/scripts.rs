@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use tracing::{debug, error, span, Level};
+
+use crate::sudoku_game::SudokuGame;
+
+use super::{Item, StatusBar};
+
+/// Errors within this long of each other are only logged once, along with how many were suppressed
+const ERROR_LOG_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a script shows its error badge after it last failed
+const ERROR_BADGE_DURATION: Duration = Duration::from_secs(5);
+/// Scripts are disabled once they have failed this many times, counting each callback at most
+/// once per frame
+const MAX_SCRIPT_ERRORS: u32 = 100;
+
+struct ScriptHealth {
+    name: String,
+    errors: u32,
+    last_error: Option<(Instant, String)>,
+    last_logged: Option<Instant>,
+    suppressed: u32,
+    disabled: bool,
+    /// The frame `counted` belongs to
+    frame: u64,
+    /// Callbacks which have already failed this frame, such as `DisplayMode`, which is called
+    /// several times per frame
+    counted: Vec<String>,
+}
+
+/// Advanced once per frame, so that a callback failing several times in a frame counts once
+static FRAME: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+    static ref SCRIPTS: Mutex<(u64, BTreeMap<u64, ScriptHealth>)> =
+        Mutex::new((0, BTreeMap::new()));
+}
+
+/// Starts tracking the health of a script, returning the id it should report errors with
+pub fn register(name: &str) -> u64 {
+    let mut scripts = SCRIPTS.lock().unwrap();
+    let id = scripts.0;
+    scripts.0 += 1;
+    scripts.1.insert(
+        id,
+        ScriptHealth {
+            name: name.to_string(),
+            errors: 0,
+            last_error: None,
+            last_logged: None,
+            suppressed: 0,
+            disabled: false,
+            frame: 0,
+            counted: Vec::new(),
+        },
+    );
+    id
+}
+
+pub fn next_frame() {
+    FRAME.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn unregister(id: u64) {
+    SCRIPTS.lock().unwrap().1.remove(&id);
+}
+
+pub fn report_error(id: u64, context: &str, message: &str) {
+    let mut scripts = SCRIPTS.lock().unwrap();
+    let Some(health) = scripts.1.get_mut(&id) else {
+        return;
+    };
+
+    let now = Instant::now();
+    health.last_error = Some((now, format!("{context}: {message}")));
+
+    let frame = FRAME.load(Ordering::Relaxed);
+    if health.frame != frame {
+        health.frame = frame;
+        health.counted.clear();
+    }
+    if health.counted.iter().any(|x| x == context) {
+        health.suppressed += 1;
+        return;
+    }
+    health.counted.push(context.to_string());
+    health.errors += 1;
+
+    if health
+        .last_logged
+        .is_some_and(|x| now.duration_since(x) < ERROR_LOG_INTERVAL)
+    {
+        health.suppressed += 1;
+    } else {
+        let suppressed = std::mem::take(&mut health.suppressed);
+        if suppressed == 0 {
+            error!("Lua '{}' {} error: {}", health.name, context, message);
+        } else {
+            error!(
+                "Lua '{}' {} error: {} ({} similar errors suppressed)",
+                health.name, context, message, suppressed
+            );
+        }
+        health.last_logged = Some(now);
+    }
+
+    if health.errors >= MAX_SCRIPT_ERRORS && !health.disabled {
+        error!(
+            "Script '{}' failed {} times, disabling...",
+            health.name, health.errors
+        );
+        health.disabled = true;
+    }
+}
+
+/// Disables a script straight away, such as when it would otherwise freeze the game
+pub fn disable(id: u64, reason: &str) {
+    let mut scripts = SCRIPTS.lock().unwrap();
+    if let Some(health) = scripts.1.get_mut(&id) {
+        if !health.disabled {
+            error!("Script '{}' disabled, as {}", health.name, reason);
+            health.disabled = true;
+        }
+    }
+}
+
+pub fn is_disabled(id: u64) -> bool {
+    SCRIPTS
+        .lock()
+        .unwrap()
+        .1
+        .get(&id)
+        .is_some_and(|x| x.disabled)
+}
+
+/// The error badge to show next to a script's status, if it failed recently
+pub fn error_badge(id: u64) -> Option<String> {
+    let scripts = SCRIPTS.lock().unwrap();
+    let health = scripts.1.get(&id)?;
+    let (at, _) = health.last_error.as_ref()?;
+
+    (at.elapsed() < ERROR_BADGE_DURATION).then(|| format!("[!{}]", health.errors))
+}
+
+fn flatten(message: &str) -> String {
+    message
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+#[derive(Default)]
+pub struct Scripts;
+
+impl Scripts {
+    fn list() -> String {
+        let scripts = SCRIPTS.lock().unwrap();
+        if scripts.1.is_empty() {
+            return "No scripts loaded".to_string();
+        }
+
+        scripts
+            .1
+            .values()
+            .map(|x| {
+                if x.disabled {
+                    format!("{} (disabled)", x.name)
+                } else if x.errors != 0 {
+                    format!("{} ({} errors)", x.name, x.errors)
+                } else {
+                    format!("{} (ok)", x.name)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The last error of the script with `name`, or the most recent error of any script
+    fn last_error(name: Option<&str>) -> String {
+        let scripts = SCRIPTS.lock().unwrap();
+        let last = scripts
+            .1
+            .values()
+            .filter(|x| name.is_none_or(|name| x.name.eq_ignore_ascii_case(name)))
+            .filter_map(|x| {
+                x.last_error
+                    .as_ref()
+                    .map(|(at, message)| (at, &x.name, message))
+            })
+            .max_by_key(|(at, _, _)| **at);
+
+        match last {
+            Some((_, name, message)) => format!("{name}: {}", flatten(message)),
+            None => "No script errors".to_string(),
+        }
+    }
+
+    /// Re-enables and clears the errors of scripts with `name`, or every script
+    fn reset(name: Option<&str>) -> usize {
+        let mut scripts = SCRIPTS.lock().unwrap();
+        let mut count = 0;
+        for health in scripts
+            .1
+            .values_mut()
+            .filter(|x| name.is_none_or(|name| x.name.eq_ignore_ascii_case(name)))
+        {
+            debug!("Resetting errors of script '{}'", health.name);
+            health.errors = 0;
+            health.last_error = None;
+            health.suppressed = 0;
+            health.disabled = false;
+            count += 1;
+        }
+        count
+    }
+}
+
+impl Item for Scripts {
+    fn name(&self) -> String {
+        "Scripts".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("Scripts [list | errors [script] | enable [script]]".to_string())
+    }
+
+    fn activated(&mut self, _game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "ScriptsActivated");
+        let _enter = span.enter();
+
+        let buffer = status_bar.buffer.clone();
+        let mut args = buffer.split_whitespace();
+        status_bar.buffer = match (args.next(), args.next()) {
+            (None | Some("list"), None) => Self::list(),
+            (Some("errors"), name) => Self::last_error(name),
+            (Some("enable"), name) => match Self::reset(name) {
+                0 => "Scripts: could not find script".to_string(),
+                count => format!("Scripts: re-enabled {count} script/s"),
+            },
+            _ => "Scripts: unknown command".to_string(),
+        };
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn disabled_after_repeated_errors() {
+        let id = register("test.lua");
+        assert!(error_badge(id).is_none());
+
+        report_error(id, "Update", "first");
+        assert_eq!(error_badge(id), Some("[!1]".to_string()));
+        assert!(!is_disabled(id));
+
+        // Repeated failures of the same callback in one frame only count once
+        for _ in 0..MAX_SCRIPT_ERRORS {
+            report_error(id, "Update", "again");
+        }
+        report_error(id, "DisplayMode", "again");
+        assert_eq!(error_badge(id), Some("[!2]".to_string()));
+
+        for _ in 2..MAX_SCRIPT_ERRORS {
+            next_frame();
+            report_error(id, "Update", "again");
+        }
+        assert!(is_disabled(id));
+
+        unregister(id);
+        assert!(!is_disabled(id));
+        assert!(error_badge(id).is_none());
+    }
+}
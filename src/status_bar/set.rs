@@ -0,0 +1,54 @@
+use tracing::{debug, span, Level};
+
+use crate::sudoku_game::SudokuGame;
+
+use super::command::{is_variable_name, split_arguments};
+use super::{Item, StatusBar};
+
+#[derive(Default)]
+pub struct Set;
+
+impl Item for Set {
+    fn name(&self) -> String {
+        "Set".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("Set ([name] [value]?)?".to_string())
+    }
+
+    fn activated(&mut self, _game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "SetActivated");
+        let _enter = span.enter();
+
+        let args = split_arguments(&status_bar.buffer);
+        let Some((name, value)) = args.split_first() else {
+            let mut variables = status_bar
+                .variables()
+                .iter()
+                .map(|(name, value)| format!("{name} = {value}"))
+                .collect::<Vec<_>>();
+            variables.sort();
+
+            status_bar.buffer = if variables.is_empty() {
+                "No variables".to_string()
+            } else {
+                variables.join(", ")
+            };
+            return;
+        };
+
+        if !is_variable_name(name) {
+            status_bar.buffer = format!("Set: invalid variable name '{name}'");
+            return;
+        }
+
+        let value = (!value.is_empty()).then(|| value.join(" "));
+        debug!("Setting variable '{}' to {:?}", name, value);
+        status_bar.set_variable(name, value);
+    }
+
+    fn display_mode(&self) -> super::DisplayMode {
+        super::DisplayMode::None
+    }
+}
@@ -45,6 +45,14 @@ impl Shorthand {
         })
     }
 
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
     pub fn apply_to_string(&self, target: &str) -> Option<String> {
         let matches_start = self.pattern.is_match_at(target, 0);
         if !matches_start {
@@ -0,0 +1,415 @@
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use tracing::{debug, error, span, trace, Level};
+
+use crate::{
+    config,
+    draw_helper::AppColour,
+    game_clock,
+    input_helper::{InputAction, InputActionContext},
+    sudoku_game::SudokuGame,
+    task_status::TaskStatus,
+};
+
+use super::{cpu_solve::SolveTask, Item, ItemOkData, ItemStatus, StatusBar};
+
+const STATS_FILE: &str = "stats.csv";
+
+/// The fewest clues a board with a single solution can have
+const MIN_UNIQUE_CLUES: usize = 17;
+
+lazy_static! {
+    /// Every saved solve, read from the stats file the first time it's needed
+    static ref RECORDS: Mutex<Option<Vec<SolveRecord>>> = Mutex::new(None);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolveRecord {
+    pub clues: u8,
+    pub time_ms: u64,
+    pub mistakes: u32,
+    pub hints: u32,
+    pub pause_ms: u64,
+}
+
+impl SolveRecord {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.trim().split(',');
+        let record = Self {
+            clues: fields.next()?.parse().ok()?,
+            time_ms: fields.next()?.parse().ok()?,
+            mistakes: fields.next()?.parse().ok()?,
+            hints: fields.next()?.parse().ok()?,
+            pause_ms: fields.next()?.parse().ok()?,
+        };
+
+        fields.next().is_none().then_some(record)
+    }
+
+    fn to_line(self) -> String {
+        format!(
+            "{},{},{},{},{}",
+            self.clues, self.time_ms, self.mistakes, self.hints, self.pause_ms
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub count: usize,
+    pub best_ms: Option<u64>,
+    pub average_ms: Option<u64>,
+}
+
+/// Summarises the solves of boards with `clues` filled tiles, which is what stands in for
+/// difficulty. Solves where hints were used are left out, so the personal best and the average
+/// are over the same solves
+pub fn summarise(records: &[SolveRecord], clues: u8) -> Summary {
+    let times = records
+        .iter()
+        .filter(|x| x.clues == clues && x.hints == 0)
+        .map(|x| x.time_ms)
+        .collect::<Vec<_>>();
+
+    let best_ms = times.iter().copied().min();
+
+    let average_ms = if times.is_empty() {
+        None
+    } else {
+        Some(times.iter().sum::<u64>() / times.len() as u64)
+    };
+
+    Summary {
+        count: times.len(),
+        best_ms,
+        average_ms,
+    }
+}
+
+fn load_records() -> Vec<SolveRecord> {
+    let Some(contents) = config::get_file(STATS_FILE, Some(&[])) else {
+        error!("Unable to load stats file");
+        return vec![];
+    };
+
+    String::from_utf8_lossy(&contents)
+        .lines()
+        .filter_map(SolveRecord::parse)
+        .collect()
+}
+
+/// Runs `f` with every saved solve, which are only read from the stats file once
+pub fn with_records<T>(f: impl FnOnce(&[SolveRecord]) -> T) -> T {
+    let mut records = RECORDS.lock().unwrap();
+    f(records.get_or_insert_with(load_records))
+}
+
+fn save_record(record: SolveRecord) {
+    RECORDS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(load_records)
+        .push(record);
+
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(config::get_file_path(STATS_FILE));
+
+    if let Err(e) = file.and_then(|mut f| writeln!(f, "{}", record.to_line())) {
+        error!("Unable to save solve to stats file: {e}");
+    }
+}
+
+pub fn format_duration_ms(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{}:{:02}.{}", secs / 60, secs % 60, (ms % 1000) / 100)
+}
+
+pub struct Stats {
+    started: Instant,
+    paused_before_start: Duration,
+    /// Set whilst only the timer is paused with `Stats pause`, as opposed to the whole game
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+    last_cells: Option<Vec<u8>>,
+    /// Solves the board from its givens, which entered numbers are checked against
+    solution: Option<SolveTask>,
+    /// Numbers entered before the solution was found, which are checked once it is
+    unchecked: Vec<(usize, u8)>,
+    mistakes: u32,
+    hints: u32,
+    finished: Option<SolveRecord>,
+    /// Set once a replay has been played back on the board, after which its solve isn't recorded
+    replayed: bool,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            paused_before_start: game_clock::paused_total(),
+            paused_at: None,
+            paused_total: Duration::ZERO,
+            last_cells: None,
+            solution: None,
+            unchecked: Vec::new(),
+            mistakes: 0,
+            hints: 0,
+            finished: None,
+            replayed: false,
+        }
+    }
+}
+
+impl Stats {
+    fn paused_duration(&self) -> Duration {
+        let game_paused = game_clock::paused_total().saturating_sub(self.paused_before_start);
+        let timer_paused = self.paused_total
+            + self
+                .paused_at
+                .map_or(Duration::ZERO, |x| Instant::now().duration_since(x));
+        game_paused + timer_paused
+    }
+
+    fn elapsed(&self) -> Duration {
+        Instant::now()
+            .duration_since(self.started)
+            .saturating_sub(self.paused_duration())
+    }
+
+    fn track_changes(&mut self, game: &SudokuGame) {
+        let cells = game.cells.iter().copied().collect::<Vec<_>>();
+        let Some(last_cells) = self.last_cells.replace(cells.clone()) else {
+            return;
+        };
+
+        let changed = cells
+            .iter()
+            .zip(last_cells.iter())
+            .enumerate()
+            .filter(|(_, (now, before))| now != before && **now != 0)
+            .map(|(i, (now, _))| (i, *now))
+            .collect::<Vec<_>>();
+
+        if changed.is_empty() {
+            return;
+        }
+
+        // Filling with auto play, or filling many cells at once (such as with CpuSolve), counts as a hint
+        let key = InputAction::get_last_input(InputActionContext::Generic, &game.input);
+        if changed.len() > 1 && game.selected_cells.len() < changed.len()
+            || matches!(key, Some(InputAction::AutoPlay))
+        {
+            self.hints += changed.len() as u32;
+            trace!("{} cell/s were filled with help", changed.len());
+            return;
+        }
+
+        self.unchecked.extend(changed);
+    }
+
+    /// Counts the unchecked numbers which don't match the solution as mistakes, once the solution
+    /// has been found. The board is solved from its givens rather than its current cells, so that
+    /// mistakes don't change the solution
+    fn check_mistakes(&mut self, game: &SudokuGame) {
+        // A board with fewer clues has several solutions, so there's nothing to check against
+        if game.cells.len() - game.unradified.len() < MIN_UNIQUE_CLUES {
+            self.unchecked.clear();
+            return;
+        }
+
+        let solution = self
+            .solution
+            .get_or_insert_with(|| SolveTask::new(&SudokuGame::new(Some(&game.givens_string()))));
+        solution.update_status();
+
+        match solution.get() {
+            TaskStatus::Done(solved) => {
+                for (idx, value) in self.unchecked.drain(..) {
+                    if solved.cells.iter().nth(idx) != Some(&value) {
+                        trace!("Mistake made at cell {idx}");
+                        self.mistakes += 1;
+                    }
+                }
+            }
+            TaskStatus::Failed if !self.unchecked.is_empty() => {
+                debug!("The board has no solution, so mistakes can't be counted");
+                self.unchecked.clear();
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self, game: &SudokuGame) {
+        if let Some(solution) = &mut self.solution {
+            solution.wait();
+        }
+        self.check_mistakes(game);
+
+        let record = SolveRecord {
+            clues: (game.cells.len() - game.unradified.len()) as u8,
+            time_ms: self.elapsed().as_millis() as u64,
+            mistakes: self.mistakes,
+            hints: self.hints,
+            pause_ms: self.paused_duration().as_millis() as u64,
+        };
+
+        debug!("Board solved: {record:?}");
+        save_record(record);
+        self.finished = Some(record);
+    }
+}
+
+impl Item for Stats {
+    fn name(&self) -> String {
+        "Stats".to_string()
+    }
+
+    fn help(&self) -> Option<String> {
+        Some("Stats (pause)?".to_string())
+    }
+
+    fn activated(&mut self, _game: &mut SudokuGame, status_bar: &mut StatusBar) {
+        let span = span!(Level::INFO, "StatsActivated");
+        let _enter = span.enter();
+
+        match status_bar.buffer.trim() {
+            "" | "pause" => {
+                if let Some(paused_at) = self.paused_at.take() {
+                    self.paused_total += Instant::now().duration_since(paused_at);
+                } else if self.finished.is_none() {
+                    self.paused_at = Some(Instant::now());
+                }
+            }
+            _ => {
+                status_bar.buffer = "Stats: unknown argument".to_string();
+            }
+        }
+    }
+
+    fn update(
+        &mut self,
+        game: &mut SudokuGame,
+        status_bar: &mut StatusBar,
+    ) -> (String, macroquad::prelude::Color) {
+        let span = span!(Level::INFO, "StatsUpdate");
+        let _enter = span.enter();
+
+        let replaying = status_bar
+            .item_with_name("Replay")
+            .is_some_and(|x| matches!(x.status(), ItemStatus::Ok(ItemOkData::ReplayPlayback)));
+        if replaying {
+            trace!("Replay is playing back, not tracking changes");
+            self.replayed = true;
+            self.last_cells = None;
+        }
+
+        if self.finished.is_none() && !self.replayed {
+            self.track_changes(game);
+            self.check_mistakes(game);
+
+            if !game.unradified.is_empty() && game.is_solved() {
+                self.finish(game);
+            }
+        }
+
+        let clues = (game.cells.len() - game.unradified.len()) as u8;
+        let summary = with_records(|x| summarise(x, clues));
+        let mut text = match self.finished {
+            Some(record) => format_duration_ms(record.time_ms),
+            None => format_duration_ms(self.elapsed().as_millis() as u64),
+        };
+
+        if self.mistakes != 0 || self.hints != 0 {
+            text.push_str(&format!(" {}m {}h", self.mistakes, self.hints));
+        }
+        if let Some(best_ms) = summary.best_ms {
+            text.push_str(&format!(
+                " {clues} clues: pb {}",
+                format_duration_ms(best_ms)
+            ));
+        }
+        if let Some(average_ms) = summary.average_ms {
+            text.push_str(&format!(" avg {}", format_duration_ms(average_ms)));
+        }
+
+        let colour = if self.finished.is_some() {
+            AppColour::StatusBarItemOkay
+        } else if game_clock::is_paused() || self.paused_at.is_some() {
+            AppColour::StatusBarItem
+        } else {
+            AppColour::StatusBarItemInProgress
+        };
+
+        (text, status_bar.drawing.colour(colour))
+    }
+
+    fn board_init(&mut self, _game: &mut SudokuGame, _status_bar: &mut StatusBar) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(clues: u8, time_ms: u64, hints: u32) -> SolveRecord {
+        SolveRecord {
+            clues,
+            time_ms,
+            mistakes: 0,
+            hints,
+            pause_ms: 0,
+        }
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let rec = SolveRecord {
+            clues: 30,
+            time_ms: 123_456,
+            mistakes: 2,
+            hints: 1,
+            pause_ms: 500,
+        };
+        assert_eq!(Some(rec), SolveRecord::parse(&rec.to_line()));
+    }
+
+    #[test]
+    fn record_invalid_lines() {
+        assert!(SolveRecord::parse("").is_none());
+        assert!(SolveRecord::parse("30,1000,0,0").is_none());
+        assert!(SolveRecord::parse("30,1000,0,0,0,0").is_none());
+        assert!(SolveRecord::parse("30,abc,0,0,0").is_none());
+    }
+
+    #[test]
+    fn summarise_by_clues() {
+        let records = [
+            record(30, 1000, 0),
+            record(30, 3000, 0),
+            record(30, 500, 2),
+            record(25, 100, 0),
+        ];
+        assert_eq!(
+            summarise(&records, 30),
+            Summary {
+                count: 2,
+                best_ms: Some(1000),
+                average_ms: Some(2000),
+            }
+        );
+        assert_eq!(
+            summarise(&records, 40),
+            Summary {
+                count: 0,
+                best_ms: None,
+                average_ms: None,
+            }
+        );
+    }
+}
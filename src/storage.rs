@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use tracing::{debug, error, span, trace, Level};
+
+use crate::config;
+
+const STORAGE_DIR: &str = "storage";
+
+/// A value which can be persisted by the storage library. Tables are stored as a list of key-value
+/// pairs, as their keys can be any of the other values
+#[derive(Clone, Debug, PartialEq)]
+pub enum StoredValue {
+    Boolean(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+    Table(Vec<(StoredValue, StoredValue)>),
+}
+
+impl StoredValue {
+    /// Values are encoded with a leading tag, and strings are length prefixed, so that nothing
+    /// needs escaping and loading a store never involves evaluating code
+    fn encode(&self, out: &mut String) {
+        match self {
+            StoredValue::Boolean(true) => out.push('T'),
+            StoredValue::Boolean(false) => out.push('F'),
+            StoredValue::Integer(x) => out.push_str(&format!("i{x};")),
+            StoredValue::Number(x) => out.push_str(&format!("n{x:?};")),
+            StoredValue::String(x) => out.push_str(&format!("s{}:{x}", x.len())),
+            StoredValue::Table(entries) => {
+                out.push('{');
+                for (key, value) in entries {
+                    key.encode(out);
+                    value.encode(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+
+    fn decode(input: &mut &str) -> Option<Self> {
+        *input = input.trim_start();
+        let mut chars = input.chars();
+        let tag = chars.next()?;
+        *input = chars.as_str();
+
+        Some(match tag {
+            'T' => StoredValue::Boolean(true),
+            'F' => StoredValue::Boolean(false),
+            'i' => StoredValue::Integer(take_until(input, ';')?.parse().ok()?),
+            'n' => StoredValue::Number(take_until(input, ';')?.parse().ok()?),
+            's' => {
+                let len = take_until(input, ':')?.parse::<usize>().ok()?;
+                let value = input.get(..len)?.to_string();
+                *input = input.get(len..)?;
+                StoredValue::String(value)
+            }
+            '{' => {
+                let mut entries = vec![];
+                loop {
+                    *input = input.trim_start();
+                    if let Some(rest) = input.strip_prefix('}') {
+                        *input = rest;
+                        break;
+                    }
+                    entries.push((Self::decode(input)?, Self::decode(input)?));
+                }
+                StoredValue::Table(entries)
+            }
+            _ => None?,
+        })
+    }
+}
+
+fn take_until<'a>(input: &mut &'a str, delimiter: char) -> Option<&'a str> {
+    let (value, rest) = input.split_once(delimiter)?;
+    *input = rest;
+    Some(value)
+}
+
+/// Each entry is written on its own line, as a string key followed by its value
+fn encode_entries(entries: &BTreeMap<String, StoredValue>) -> String {
+    let mut out = String::new();
+    for (key, value) in entries {
+        StoredValue::String(key.clone()).encode(&mut out);
+        out.push(' ');
+        value.encode(&mut out);
+        out.push('\n');
+    }
+    out
+}
+
+fn decode_entries(mut input: &str) -> Option<BTreeMap<String, StoredValue>> {
+    let mut entries = BTreeMap::new();
+    while !input.trim_start().is_empty() {
+        let StoredValue::String(key) = StoredValue::decode(&mut input)? else {
+            return None;
+        };
+        entries.insert(key, StoredValue::decode(&mut input)?);
+    }
+    Some(entries)
+}
+
+/// A key-value store belonging to a single script. Changes are kept in memory until `flush` is
+/// called, which the script does once per frame, or until the store is dropped
+pub struct Store {
+    path: PathBuf,
+    entries: BTreeMap<String, StoredValue>,
+    dirty: bool,
+}
+
+impl Store {
+    pub fn load(namespace: &str) -> Self {
+        let span = span!(Level::INFO, "StorageLoad");
+        let _enter = span.enter();
+
+        let name = namespace
+            .chars()
+            .map(|x| {
+                if x.is_ascii_alphanumeric() || matches!(x, '.' | '-' | '_') {
+                    x
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        let path = config::get_file_path(&format!("{STORAGE_DIR}/{name}.store"));
+
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => decode_entries(&contents).unwrap_or_else(|| {
+                error!("Storage file '{}' is malformed, ignoring", path.display());
+                BTreeMap::new()
+            }),
+            Err(_) => {
+                debug!("No storage file at '{}'", path.display());
+                BTreeMap::new()
+            }
+        };
+
+        trace!(
+            "Loaded {} stored entries for '{}'",
+            entries.len(),
+            namespace
+        );
+        Self {
+            path,
+            entries,
+            dirty: false,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&StoredValue> {
+        self.entries.get(key)
+    }
+
+    pub fn set(&mut self, key: &str, value: StoredValue) {
+        if self.entries.get(key) != Some(&value) {
+            self.entries.insert(key.to_string(), value);
+            self.dirty = true;
+        }
+    }
+
+    pub fn delete(&mut self, key: &str) {
+        if self.entries.remove(key).is_some() {
+            self.dirty = true;
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.keys()
+    }
+
+    /// Writes the store to its file if it has changed since it was last written
+    pub fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        trace!("Saving storage file '{}'", self.path.display());
+
+        if let Some(parent) = self.path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        if let Err(e) = std::fs::write(&self.path, encode_entries(&self.entries)) {
+            error!("Unable to save storage file '{}': {e}", self.path.display());
+        }
+    }
+}
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip() {
+        let mut entries = BTreeMap::new();
+        entries.insert("best".to_string(), StoredValue::Integer(-1234));
+        entries.insert("ratio".to_string(), StoredValue::Number(0.5));
+        entries.insert(
+            "with spaces\nand lines".to_string(),
+            StoredValue::String("s3:}{ T\n".to_string()),
+        );
+        entries.insert(
+            "nested".to_string(),
+            StoredValue::Table(vec![
+                (StoredValue::Integer(1), StoredValue::Boolean(true)),
+                (
+                    StoredValue::String("inner".to_string()),
+                    StoredValue::Table(vec![(
+                        StoredValue::Boolean(false),
+                        StoredValue::Number(1e20),
+                    )]),
+                ),
+            ]),
+        );
+
+        assert_eq!(decode_entries(&encode_entries(&entries)), Some(entries));
+    }
+
+    #[test]
+    fn malformed_entries() {
+        assert_eq!(decode_entries(""), Some(BTreeMap::new()));
+        assert_eq!(decode_entries("s3:key"), None);
+        assert_eq!(decode_entries("i1; T"), None);
+        assert_eq!(decode_entries("s3:key {i1;"), None);
+        assert_eq!(decode_entries("s3:key s10:short"), None);
+        assert_eq!(decode_entries("s3:key print('hi')"), None);
+    }
+}
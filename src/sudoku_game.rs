@@ -14,8 +14,11 @@ pub enum ResetSignal {
 
 pub struct SudokuGame {
     pub cells: Array2<u8>,
+    /// Colour mark of each cell, where 0 is unmarked. Marks only live as long as the game, so they
+    /// survive a soft reset but not a new board, and they aren't saved
+    pub marks: Vec<u8>,
     pub unradified: Vec<u8>,
-    pub selected_cell: Option<(u32, u32)>,
+    pub selected_cells: Vec<(u32, u32)>,
     pub reset_signalled: ResetSignal,
     pub padding_progress: f32,
     pub input: InputState,
@@ -26,8 +29,9 @@ impl Clone for SudokuGame {
     fn clone(&self) -> Self {
         Self {
             cells: self.cells.clone(),
+            marks: self.marks.clone(),
             unradified: self.unradified.clone(),
-            selected_cell: self.selected_cell,
+            selected_cells: self.selected_cells.clone(),
             reset_signalled: self.reset_signalled.clone(),
             padding_progress: 0.0,
             input: InputState::default(),
@@ -62,9 +66,10 @@ impl SudokuGame {
         );
 
         SudokuGame {
+            marks: vec![0; cells.len()],
             cells,
             unradified,
-            selected_cell: None,
+            selected_cells: Vec::new(),
             reset_signalled: ResetSignal::None,
             padding_progress: 0.0,
             input: InputState::default(),
@@ -80,6 +85,85 @@ impl SudokuGame {
         self.wanted_commands.clear();
     }
 
+    /// The most recently selected cell, which is used for keyboard movement and highlighting
+    pub fn selected_cell(&self) -> Option<(u32, u32)> {
+        self.selected_cells.last().copied()
+    }
+
+    pub fn is_selected(&self, pos: (u32, u32)) -> bool {
+        self.selected_cells.contains(&pos)
+    }
+
+    pub fn select(&mut self, pos: (u32, u32)) {
+        self.selected_cells.clear();
+        self.selected_cells.push(pos);
+    }
+
+    pub fn add_to_selection(&mut self, pos: (u32, u32)) {
+        self.selected_cells.retain(|x| *x != pos);
+        self.selected_cells.push(pos);
+    }
+
+    /// Replaces the selection with `cells`, keeping the last of any repeated cell so that every
+    /// cell is only selected once
+    pub fn set_selection(&mut self, cells: impl IntoIterator<Item = (u32, u32)>) {
+        self.selected_cells.clear();
+        for pos in cells {
+            self.add_to_selection(pos);
+        }
+    }
+
+    pub fn toggle_selected(&mut self, pos: (u32, u32)) {
+        if self.is_selected(pos) {
+            self.selected_cells.retain(|x| *x != pos);
+        } else {
+            self.selected_cells.push(pos);
+        }
+    }
+
+    pub fn select_range(&mut self, to: (u32, u32)) {
+        let Some(from) = self.selected_cell() else {
+            self.select(to);
+            return;
+        };
+
+        for y in from.1.min(to.1)..=from.1.max(to.1) {
+            for x in from.0.min(to.0)..=from.0.max(to.0) {
+                if !self.is_selected((x, y)) {
+                    self.selected_cells.push((x, y));
+                }
+            }
+        }
+        self.add_to_selection(to);
+    }
+
+    pub fn select_all_with_number(&mut self, number: u8) {
+        let size = self.cells.shape()[1] as u32;
+        let cells = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| **cell == number)
+            .map(|(i, _)| Self::idx_pos_to_xy(i as u32, size))
+            .collect::<Vec<_>>();
+        self.set_selection(cells);
+    }
+
+    /// Sets the mark of every selected cell to `mark`, or clears them if they all already have it
+    pub fn toggle_mark_on_selection(&mut self, mark: u8) {
+        let size = self.cells.shape()[1] as u32;
+        let selected = self
+            .selected_cells
+            .iter()
+            .map(|(x, y)| Self::xy_pos_to_idx(*x, *y, size) as usize)
+            .collect::<Vec<_>>();
+        let all_marked = selected.iter().all(|idx| self.marks[*idx] == mark);
+
+        for idx in selected {
+            self.marks[idx] = if all_marked { 0 } else { mark };
+        }
+    }
+
     #[instrument]
     pub(crate) fn generate_cells_from_string(cell_str: &str) -> Option<Array2<u8>> {
         let mut cells = Array2::zeros((9, 9));
@@ -214,6 +298,15 @@ impl SudokuGame {
         buf
     }
 
+    /// The board as it was before any tiles were placed, in the same format as `board_string`
+    pub(crate) fn givens_string(&self) -> String {
+        let mut cells = self.cells.clone();
+        for idx in &self.unradified {
+            *cells.iter_mut().nth(*idx as usize).unwrap() = 0;
+        }
+        cells.iter().map(ToString::to_string).collect()
+    }
+
     #[allow(clippy::inline_always)]
     #[inline(always)]
     pub fn xy_pos_to_idx(x: u32, y: u32, size: u32) -> u32 {
@@ -297,17 +390,17 @@ impl SudokuGame {
         highlight_cells
     }
 
-    pub fn rows(&self) -> Vec<ArrayView<u8, Ix1>> {
+    pub fn rows(&self) -> Vec<ArrayView<'_, u8, Ix1>> {
         (0..9)
             .map(|i| self.cells.index_axis(Axis(0), i))
             .collect::<Vec<_>>()
     }
-    pub fn cols(&self) -> Vec<ArrayView<u8, Ix1>> {
+    pub fn cols(&self) -> Vec<ArrayView<'_, u8, Ix1>> {
         (0..9)
             .map(|i| self.cells.index_axis(Axis(1), i))
             .collect::<Vec<_>>()
     }
-    pub fn boxes(&self) -> Array2<ArrayView2<u8>> {
+    pub fn boxes(&self) -> Array2<ArrayView2<'_, u8>> {
         let boxes = (0..3)
             .flat_map(|i| {
                 (0..3).map(move |j| self.cells.slice(s![i * 3..(i + 1) * 3, j * 3..(j + 1) * 3]))